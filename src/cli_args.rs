@@ -1,7 +1,13 @@
 use std::path::PathBuf;
 
 use crate::constants::*;
+use crate::count_options::{CountOptions, LineTerminator, WhitespaceMode, WordMode};
 use crate::display_options::DisplayOptions;
+use crate::distinct::{DEFAULT_PRECISION, MAX_PRECISION, MIN_PRECISION};
+use crate::glob::PathFilter;
+use crate::output::OutputFormat;
+use crate::walk::WalkOptions;
+use crate::word_freq::WordFreqOptions;
 
 /// Print a message saying `arg` is not a recognized option and exit with
 /// `EXIT_FAILURE`.
@@ -28,6 +34,12 @@ fn parse_long_option(arg: &str) -> DisplayOptions {
         "--words" => DisplayOptions::with_words_only(),
         "--chars" => DisplayOptions::with_chars_only(),
         "--bytes" => DisplayOptions::with_bytes_only(),
+        "--max-line-length" => DisplayOptions::with_max_line_length_only(),
+        "--blank-lines" => DisplayOptions::with_blank_lines_only(),
+        "--non-blank-lines" => DisplayOptions::with_non_blank_lines_only(),
+        "--graphemes" => DisplayOptions::with_graphemes_only(),
+        "--columns" => DisplayOptions::with_columns_only(),
+        "--line-endings" => DisplayOptions::with_line_endings_only(),
         _ => {
             invalid_option_exit(arg);
             unreachable!()
@@ -54,54 +66,393 @@ fn parse_short_option(arg: &str) -> DisplayOptions {
         "-m" => DisplayOptions::with_chars_only(),
         // `-c` is bytes
         "-c" => DisplayOptions::with_bytes_only(),
+        // `-L` is the maximum line length
+        "-L" => DisplayOptions::with_max_line_length_only(),
         _ => {
             invalid_option_exit(arg);
             unreachable!()
         }
     }
 }
-/// Parse the command lines arguments and return a tuple consisting
-/// of the display options, the paths to perform counting on, and
-/// whether or not to also read from stdin.
-pub fn parse_args(args: &[String]) -> (DisplayOptions, Vec<PathBuf>, bool) {
-    let mut cli_option_seen = false;
-    let mut read_stdin = false;
-    let mut display_options = DisplayOptions::default();
-    let mut paths: Vec<PathBuf> = Vec::new();
-    for arg in args {
-        // check if `arg` starts with `-`
-        if &arg[0..=0] == "-" {
-            // if the argument length is at least two then we have a
-            // a potential CLI option
-            if arg.len() >= 2 {
-                // we can set this to true because if arg isn't a valid
-                // CLI option, we exit the program
-                cli_option_seen = true;
-                if &arg[0..=1] == "--" {
-                    display_options.join_mut(&parse_long_option(arg));
-                } else {
-                    display_options.join_mut(&parse_short_option(arg));
+/// When to print the line with the total counts of all inputs.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum TotalMode {
+    /// only when there is more than one input
+    #[default]
+    Auto,
+    /// even when there is only one input
+    Always,
+    /// print the total without the counts of each input
+    Only,
+    /// never
+    Never,
+}
+
+impl TotalMode {
+    /// Returns `true` if the total should be printed for `num_inputs` inputs.
+    pub fn print_total(&self, num_inputs: usize) -> bool {
+        match self {
+            TotalMode::Auto => num_inputs > 1,
+            TotalMode::Always | TotalMode::Only => true,
+            TotalMode::Never => false,
+        }
+    }
+
+    /// Returns `true` if the counts of each input should be printed.
+    pub fn print_inputs(&self) -> bool {
+        *self != TotalMode::Only
+    }
+}
+
+/// The options and inputs requested on the command line.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CliArgs {
+    /// which counts to display
+    pub display_options: DisplayOptions,
+    /// how to count the input
+    pub count_options: CountOptions,
+    /// the paths to perform counting on
+    pub paths: Vec<PathBuf>,
+    /// whether or not to also read from stdin
+    pub read_stdin: bool,
+    /// whether stdin is only read because no paths were given
+    pub implicit_stdin: bool,
+    /// the number of files to count in parallel
+    pub jobs: usize,
+    /// whether to split each regular file into byte ranges counted on
+    /// `jobs` threads
+    pub split_files: bool,
+    /// whether to memory map regular files instead of reading them
+    pub mmap: bool,
+    /// when to print the total counts
+    pub total: TotalMode,
+    /// the format to print the counts in
+    pub format: OutputFormat,
+    /// whether to report code, comment and blank lines per language
+    pub code_stats: bool,
+    /// how to report the most frequent words, if they are reported
+    pub word_freq: Option<WordFreqOptions>,
+    /// whether to count the files in directories instead of the directories
+    pub recursive: bool,
+    /// how to walk directories with `recursive`
+    pub walk_options: WalkOptions,
+    /// the `--include` and `--exclude` patterns files have to pass
+    pub filter: PathFilter,
+    /// a file containing NUL terminated paths to count instead of `paths`,
+    /// `-` for stdin
+    pub files0_from: Option<PathBuf>,
+}
+
+impl Default for CliArgs {
+    fn default() -> Self {
+        Self {
+            display_options: DisplayOptions::default(),
+            count_options: CountOptions::default(),
+            paths: Vec::new(),
+            read_stdin: false,
+            implicit_stdin: false,
+            jobs: 1,
+            split_files: false,
+            mmap: false,
+            total: TotalMode::default(),
+            format: OutputFormat::default(),
+            code_stats: false,
+            word_freq: None,
+            recursive: false,
+            walk_options: WalkOptions::default(),
+            filter: PathFilter::default(),
+            files0_from: None,
+        }
+    }
+}
+
+/// Print a message saying `option` requires an argument and exit with
+/// `EXIT_FAILURE`.
+fn missing_argument_exit(option: &str) -> ! {
+    eprintln!("{}: option '{}' requires an argument", PROGRAM, option);
+    std::process::exit(EXIT_FAILURE as i32);
+}
+
+/// Print a message saying `value` is not a valid argument for `option`
+/// and exit with `EXIT_FAILURE`.
+fn invalid_argument_exit(option: &str, value: &str) -> ! {
+    eprintln!("{}: invalid argument '{}' for '{}'", PROGRAM, value, option);
+    std::process::exit(EXIT_FAILURE as i32);
+}
+
+/// Return the argument of `option`, which is either `value` when the
+/// option was given as `--option=value`, or the next command line argument.
+fn option_argument<'a, I: Iterator<Item = &'a String>>(
+    option: &str,
+    value: Option<&'a str>,
+    args: &mut I,
+) -> &'a str {
+    match value {
+        Some(value) => value,
+        None => match args.next() {
+            Some(value) => value,
+            None => missing_argument_exit(option),
+        },
+    }
+}
+
+/// Parse the number of jobs given to `option`, which must be at least 1.
+fn parse_jobs(option: &str, value: &str) -> usize {
+    match value.parse::<usize>() {
+        Ok(jobs) if jobs >= 1 => jobs,
+        _ => invalid_argument_exit(option, value),
+    }
+}
+
+/// Parse the tab width given to `option`, which must be at least 1.
+fn parse_tab_width(option: &str, value: &str) -> usize {
+    match value.parse::<usize>() {
+        Ok(width) if width >= 1 => width,
+        _ => invalid_argument_exit(option, value),
+    }
+}
+
+/// Parse the precision of the distinct words sketch given to `option`, which
+/// must be between `MIN_PRECISION` and `MAX_PRECISION`.
+fn parse_precision(option: &str, value: &str) -> u8 {
+    match value.parse::<u8>() {
+        Ok(precision) if (MIN_PRECISION..=MAX_PRECISION).contains(&precision) => precision,
+        _ => invalid_argument_exit(option, value),
+    }
+}
+
+/// Parse the non-negative number given to `option`, such as a maximum depth.
+fn parse_number(option: &str, value: &str) -> usize {
+    match value.parse::<usize>() {
+        Ok(number) => number,
+        _ => invalid_argument_exit(option, value),
+    }
+}
+
+/// Print a message saying `operand` can't be given along with
+/// `--files0-from` and exit with `EXIT_FAILURE`.
+fn extra_operand_exit(operand: &str) -> ! {
+    eprintln!(
+        "{}: extra operand '{}'\nfile operands cannot be combined with --files0-from",
+        PROGRAM, operand
+    );
+    std::process::exit(EXIT_FAILURE as i32);
+}
+
+/// Print a message saying `option` can't be used with `--format=format`
+/// and exit with `EXIT_FAILURE`.
+fn unsupported_format_exit(option: &str, format: &str) -> ! {
+    eprintln!(
+        "{}: option '{}' can't be used with --format={}",
+        PROGRAM, option, format
+    );
+    std::process::exit(EXIT_FAILURE as i32);
+}
+
+/// Parse the `--format` argument `value`.
+fn parse_format(option: &str, value: &str) -> OutputFormat {
+    match value {
+        "text" => OutputFormat::Text,
+        "json" => OutputFormat::Json,
+        "csv" => OutputFormat::Csv,
+        "tsv" => OutputFormat::Tsv,
+        _ => invalid_argument_exit(option, value),
+    }
+}
+
+/// Parse the `--word-mode` argument `value`.
+fn parse_word_mode(option: &str, value: &str) -> WordMode {
+    match value {
+        "whitespace" => WordMode::Whitespace,
+        "uax29" => WordMode::Uax29,
+        _ => invalid_argument_exit(option, value),
+    }
+}
+
+/// Parse the `--whitespace` argument `value`.
+fn parse_whitespace(option: &str, value: &str) -> WhitespaceMode {
+    match value {
+        "unicode" => WhitespaceMode::Unicode,
+        "ascii" => WhitespaceMode::Ascii,
+        "posix" => WhitespaceMode::Posix,
+        _ => invalid_argument_exit(option, value),
+    }
+}
+
+/// Parse the `--line-terminator` argument `value`.
+fn parse_line_terminator(option: &str, value: &str) -> LineTerminator {
+    match value {
+        "lf" => LineTerminator::Lf,
+        "crlf" => LineTerminator::Crlf,
+        "cr" => LineTerminator::Cr,
+        "any" => LineTerminator::Any,
+        _ => invalid_argument_exit(option, value),
+    }
+}
+
+/// Parse the `--total` argument `value`.
+fn parse_total(option: &str, value: &str) -> TotalMode {
+    match value {
+        "auto" => TotalMode::Auto,
+        "always" => TotalMode::Always,
+        "only" => TotalMode::Only,
+        "never" => TotalMode::Never,
+        _ => invalid_argument_exit(option, value),
+    }
+}
+
+/// Parse the command lines arguments and return the display options,
+/// the paths to perform counting on, whether or not to also read from
+/// stdin, and any other options requested.
+pub fn parse_args(args: &[String]) -> CliArgs {
+    let mut cli_args = CliArgs::default();
+    // the word frequency modifiers may come before or after `--word-freq`
+    let mut word_freq = WordFreqOptions::default();
+    let mut report_word_freq = false;
+    let mut distinct_words = false;
+    let mut distinct_precision = DEFAULT_PRECISION;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        // `-` on its own means we need to read stdin
+        if arg == "-" {
+            cli_args.read_stdin = true;
+        }
+        // a long option, possibly of the form `--option=value`
+        else if arg.starts_with("--") {
+            let (option, value) = match arg.split_once('=') {
+                Some((option, value)) => (option, Some(value)),
+                None => (arg.as_str(), None),
+            };
+            match option {
+                "--split-files" => cli_args.split_files = true,
+                "--mmap" => cli_args.mmap = true,
+                "--code-stats" => cli_args.code_stats = true,
+                // the number of words is optional, so it can only be given
+                // as `--word-freq=N`
+                "--word-freq" => {
+                    report_word_freq = true;
+                    if let Some(top) = value {
+                        word_freq.top = parse_number(option, top);
+                    }
+                }
+                "--distinct-words" => distinct_words = true,
+                "--distinct-precision" => {
+                    let precision = option_argument(option, value, &mut args);
+                    distinct_precision = parse_precision(option, precision);
+                }
+                "--fold-case" => word_freq.fold_case = true,
+                "--strip-punctuation" => word_freq.strip_punctuation = true,
+                "--min-word-length" => {
+                    let length = option_argument(option, value, &mut args);
+                    word_freq.min_length = parse_number(option, length);
+                }
+                "--recursive" => cli_args.recursive = true,
+                "--follow-symlinks" => cli_args.walk_options.follow_symlinks = true,
+                "--hidden" => cli_args.walk_options.hidden = true,
+                "--one-file-system" => cli_args.walk_options.one_file_system = true,
+                "--no-ignore" => cli_args.walk_options.no_ignore = true,
+                "--include" => cli_args
+                    .filter
+                    .include(option_argument(option, value, &mut args)),
+                "--exclude" => cli_args
+                    .filter
+                    .exclude(option_argument(option, value, &mut args)),
+                "--max-depth" => {
+                    let depth = option_argument(option, value, &mut args);
+                    cli_args.walk_options.max_depth = Some(parse_number(option, depth));
+                }
+                "--whitespace" => {
+                    cli_args.count_options.whitespace =
+                        parse_whitespace(option, option_argument(option, value, &mut args))
+                }
+                "--line-terminator" => {
+                    cli_args.count_options.line_terminator =
+                        parse_line_terminator(option, option_argument(option, value, &mut args))
+                }
+                "--tab-width" => {
+                    cli_args.count_options.tab_width =
+                        parse_tab_width(option, option_argument(option, value, &mut args))
                 }
+                "--word-mode" => {
+                    cli_args.count_options.word_mode =
+                        parse_word_mode(option, option_argument(option, value, &mut args))
+                }
+                "--format" => {
+                    cli_args.format =
+                        parse_format(option, option_argument(option, value, &mut args))
+                }
+                "--files0-from" => {
+                    let from = option_argument(option, value, &mut args);
+                    cli_args.files0_from = Some(PathBuf::from(from));
+                }
+                "--total" => {
+                    cli_args.total = parse_total(option, option_argument(option, value, &mut args))
+                }
+                "--jobs" => {
+                    cli_args.jobs = parse_jobs(option, option_argument(option, value, &mut args))
+                }
+                _ => cli_args.display_options.join_mut(&parse_long_option(arg)),
             }
-            // otherwise `arg` is `-` so we need to read stdin
-            else {
-                read_stdin = true;
+        }
+        // a short option
+        else if arg.starts_with('-') {
+            match arg.as_str() {
+                "-j" => cli_args.jobs = parse_jobs(arg, option_argument(arg, None, &mut args)),
+                "-r" => cli_args.recursive = true,
+                _ => cli_args.display_options.join_mut(&parse_short_option(arg)),
             }
         }
         // otherwise we have a potential path to read
         else {
-            paths.push(PathBuf::from(arg));
+            cli_args.paths.push(PathBuf::from(arg));
         }
     }
 
-    // if we never saw a CLI option use the wc default options
-    if !cli_option_seen {
-        display_options = DisplayOptions::default_options();
+    if distinct_words {
+        let options = DisplayOptions::with_distinct_words_only(distinct_precision);
+        cli_args.display_options.join_mut(&options);
+    }
+
+    // if we never saw a display option use the wc default options
+    if cli_args.display_options.all_off() {
+        cli_args.display_options = DisplayOptions::default_options();
+    }
+
+    if report_word_freq {
+        cli_args.word_freq = Some(word_freq);
+    }
+
+    // a CSV or TSV table has no room for the per language or per word tables
+    let extra_table = if cli_args.code_stats {
+        Some("--code-stats")
+    } else if cli_args.word_freq.is_some() {
+        Some("--word-freq")
+    } else {
+        None
+    };
+    if let Some(option) = extra_table {
+        match cli_args.format {
+            OutputFormat::Csv => unsupported_format_exit(option, "csv"),
+            OutputFormat::Tsv => unsupported_format_exit(option, "tsv"),
+            OutputFormat::Text | OutputFormat::Json => {}
+        }
+    }
+
+    // the paths come from the `--files0-from` file instead
+    if cli_args.files0_from.is_some() {
+        if let Some(path) = cli_args.paths.first() {
+            extra_operand_exit(&path.to_string_lossy());
+        }
+        if cli_args.read_stdin {
+            extra_operand_exit("-");
+        }
+        return cli_args;
     }
 
     // if we didn't find any file paths, read from stdin
-    read_stdin = read_stdin || paths.is_empty();
-    (display_options, paths, read_stdin)
+    cli_args.implicit_stdin = !cli_args.read_stdin && cli_args.paths.is_empty();
+    cli_args.read_stdin = cli_args.read_stdin || cli_args.implicit_stdin;
+    cli_args
 }
 
 #[cfg(test)]
@@ -114,6 +465,10 @@ mod test {
         assert_eq!(parse_short_option("-w"), DisplayOptions::with_words_only());
         assert_eq!(parse_short_option("-m"), DisplayOptions::with_chars_only());
         assert_eq!(parse_short_option("-c"), DisplayOptions::with_bytes_only());
+        assert_eq!(
+            parse_short_option("-L"),
+            DisplayOptions::with_max_line_length_only()
+        );
     }
 
     #[test]
@@ -134,12 +489,39 @@ mod test {
             parse_long_option("--bytes"),
             DisplayOptions::with_bytes_only()
         );
+        assert_eq!(
+            parse_long_option("--max-line-length"),
+            DisplayOptions::with_max_line_length_only()
+        );
+        assert_eq!(
+            parse_long_option("--blank-lines"),
+            DisplayOptions::with_blank_lines_only()
+        );
+        assert_eq!(
+            parse_long_option("--non-blank-lines"),
+            DisplayOptions::with_non_blank_lines_only()
+        );
+        assert_eq!(
+            parse_long_option("--graphemes"),
+            DisplayOptions::with_graphemes_only()
+        );
+        assert_eq!(
+            parse_long_option("--columns"),
+            DisplayOptions::with_columns_only()
+        );
+        assert_eq!(
+            parse_long_option("--line-endings"),
+            DisplayOptions::with_line_endings_only()
+        );
     }
 
     #[test]
     fn test_parse_args() {
         let res = parse_args(&[String::from("--lines"), String::from("-m")]);
-        assert_eq!(res.0, DisplayOptions::new(true, false, true, false));
+        assert_eq!(
+            res.display_options,
+            DisplayOptions::new(true, false, true, false)
+        );
 
         let res = parse_args(&[
             String::from("--lines"),
@@ -148,33 +530,272 @@ mod test {
             String::from("test"),
             String::from("--bytes"),
         ]);
-        assert_eq!(res.0, DisplayOptions::new(true, false, true, true));
-        assert_eq!(res.1, vec![PathBuf::from("test")]);
-        assert_eq!(res.2, true);
+        assert_eq!(
+            res.display_options,
+            DisplayOptions::new(true, false, true, true)
+        );
+        assert_eq!(res.paths, vec![PathBuf::from("test")]);
+        assert!(res.read_stdin);
+        assert!(!res.implicit_stdin);
         let res = parse_args(&[
             String::from("test"),
             String::from("a"),
             String::from("-"),
             String::from("1234"),
         ]);
-        assert_eq!(res.0, DisplayOptions::default_options());
+        assert_eq!(res.display_options, DisplayOptions::default_options());
         assert_eq!(
-            res.1,
+            res.paths,
             vec![
                 PathBuf::from("test"),
                 PathBuf::from("a"),
                 PathBuf::from("1234")
             ]
         );
-        assert_eq!(res.2, true);
+        assert!(res.read_stdin);
 
         let res = parse_args(&[
             String::from("-c"),
             String::from("--chars"),
             String::from("-w"),
         ]);
-        assert_eq!(res.0, DisplayOptions::new(false, true, true, true));
-        assert!(res.1.is_empty());
-        assert_eq!(res.2, true);
+        assert_eq!(
+            res.display_options,
+            DisplayOptions::new(false, true, true, true)
+        );
+        assert!(res.paths.is_empty());
+        assert!(res.read_stdin);
+        assert!(res.implicit_stdin);
+    }
+
+    #[test]
+    fn test_parse_jobs() {
+        let res = parse_args(&[String::from("a"), String::from("b")]);
+        assert_eq!(res.jobs, 1);
+
+        let res = parse_args(&[
+            String::from("--jobs=4"),
+            String::from("a"),
+            String::from("-l"),
+        ]);
+        assert_eq!(res.jobs, 4);
+        assert_eq!(res.display_options, DisplayOptions::with_lines_only());
+        assert_eq!(res.paths, vec![PathBuf::from("a")]);
+
+        let res = parse_args(&[String::from("-j"), String::from("8"), String::from("a")]);
+        assert_eq!(res.jobs, 8);
+        assert_eq!(res.display_options, DisplayOptions::default_options());
+        assert_eq!(res.paths, vec![PathBuf::from("a")]);
+
+        let res = parse_args(&[String::from("--jobs"), String::from("2")]);
+        assert_eq!(res.jobs, 2);
+        assert!(res.paths.is_empty());
+        assert!(res.read_stdin);
+
+        let res = parse_args(&[
+            String::from("--split-files"),
+            String::from("-j"),
+            String::from("4"),
+        ]);
+        assert!(res.split_files);
+        assert_eq!(res.display_options, DisplayOptions::default_options());
+
+        let res = parse_args(&[String::from("--mmap"), String::from("-w")]);
+        assert!(res.mmap);
+        assert_eq!(res.display_options, DisplayOptions::with_words_only());
+    }
+
+    #[test]
+    fn test_parse_total() {
+        let res = parse_args(&[String::from("a")]);
+        assert_eq!(res.total, TotalMode::Auto);
+        let res = parse_args(&[String::from("--total=always"), String::from("a")]);
+        assert_eq!(res.total, TotalMode::Always);
+        assert_eq!(res.paths, vec![PathBuf::from("a")]);
+        let res = parse_args(&[String::from("--total"), String::from("only")]);
+        assert_eq!(res.total, TotalMode::Only);
+        assert!(res.paths.is_empty());
+        let res = parse_args(&[String::from("--total=never"), String::from("-l")]);
+        assert_eq!(res.total, TotalMode::Never);
+        assert_eq!(res.display_options, DisplayOptions::with_lines_only());
+    }
+
+    #[test]
+    fn test_total_mode() {
+        assert!(!TotalMode::Auto.print_total(1));
+        assert!(TotalMode::Auto.print_total(2));
+        assert!(TotalMode::Always.print_total(1));
+        assert!(TotalMode::Only.print_total(1));
+        assert!(!TotalMode::Never.print_total(3));
+        assert!(TotalMode::Auto.print_inputs());
+        assert!(!TotalMode::Only.print_inputs());
+    }
+
+    #[test]
+    fn test_parse_files0_from() {
+        let res = parse_args(&[String::from("--files0-from=list"), String::from("-w")]);
+        assert_eq!(res.files0_from, Some(PathBuf::from("list")));
+        assert_eq!(res.display_options, DisplayOptions::with_words_only());
+        assert!(res.paths.is_empty());
+        // stdin is only read if it is the list of files
+        assert!(!res.read_stdin);
+
+        let res = parse_args(&[String::from("--files0-from"), String::from("-")]);
+        assert_eq!(res.files0_from, Some(PathBuf::from("-")));
+        assert!(!res.read_stdin);
+    }
+
+    #[test]
+    fn test_parse_format() {
+        let res = parse_args(&[String::from("a")]);
+        assert_eq!(res.format, OutputFormat::Text);
+        let res = parse_args(&[String::from("--format=json"), String::from("a")]);
+        assert_eq!(res.format, OutputFormat::Json);
+        let res = parse_args(&[String::from("--format=tsv")]);
+        assert_eq!(res.format, OutputFormat::Tsv);
+        let res = parse_args(&[String::from("--format"), String::from("text")]);
+        assert_eq!(res.format, OutputFormat::Text);
+        assert!(res.paths.is_empty());
+    }
+
+    #[test]
+    fn test_parse_recursive() {
+        let res = parse_args(&[String::from("src")]);
+        assert!(!res.recursive);
+        assert_eq!(res.walk_options, WalkOptions::default());
+
+        let res = parse_args(&[
+            String::from("-r"),
+            String::from("--follow-symlinks"),
+            String::from("--hidden"),
+            String::from("--one-file-system"),
+            String::from("--no-ignore"),
+            String::from("--max-depth=3"),
+            String::from("src"),
+        ]);
+        assert!(res.recursive);
+        assert_eq!(
+            res.walk_options,
+            WalkOptions {
+                follow_symlinks: true,
+                max_depth: Some(3),
+                hidden: true,
+                one_file_system: true,
+                no_ignore: true,
+            }
+        );
+        assert_eq!(res.paths, vec![PathBuf::from("src")]);
+
+        let res = parse_args(&[
+            String::from("--recursive"),
+            String::from("--max-depth"),
+            String::from("0"),
+        ]);
+        assert!(res.recursive);
+        assert_eq!(res.walk_options.max_depth, Some(0));
+        assert!(res.implicit_stdin);
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        let res = parse_args(&[
+            String::from("--include=*.rs"),
+            String::from("--include"),
+            String::from("*.toml"),
+            String::from("--exclude=target/**"),
+            String::from("src"),
+        ]);
+        let mut filter = PathFilter::default();
+        filter.include("*.rs");
+        filter.include("*.toml");
+        filter.exclude("target/**");
+        assert_eq!(res.filter, filter);
+        assert_eq!(res.paths, vec![PathBuf::from("src")]);
+    }
+
+    #[test]
+    fn test_parse_code_stats() {
+        let res = parse_args(&[String::from("a.rs")]);
+        assert!(!res.code_stats);
+        let res = parse_args(&[String::from("--code-stats"), String::from("--format=json")]);
+        assert!(res.code_stats);
+    }
+
+    #[test]
+    fn test_parse_word_freq() {
+        let res = parse_args(&[String::from("--fold-case")]);
+        assert_eq!(res.word_freq, None);
+        let res = parse_args(&[String::from("--word-freq")]);
+        assert_eq!(res.word_freq, Some(WordFreqOptions::default()));
+        let res = parse_args(&[
+            String::from("--fold-case"),
+            String::from("--word-freq=3"),
+            String::from("--min-word-length"),
+            String::from("2"),
+            String::from("--strip-punctuation"),
+        ]);
+        let expected = WordFreqOptions {
+            top: 3,
+            fold_case: true,
+            strip_punctuation: true,
+            min_length: 2,
+        };
+        assert_eq!(res.word_freq, Some(expected));
+    }
+
+    #[test]
+    fn test_parse_distinct_words() {
+        let res = parse_args(&[String::from("--distinct-words")]);
+        assert_eq!(
+            res.display_options,
+            DisplayOptions::with_distinct_words_only(DEFAULT_PRECISION)
+        );
+        let res = parse_args(&[
+            String::from("--distinct-precision=16"),
+            String::from("-w"),
+            String::from("--distinct-words"),
+        ]);
+        let expected =
+            DisplayOptions::with_words_only().join(&DisplayOptions::with_distinct_words_only(16));
+        assert_eq!(res.display_options, expected);
+        // the precision alone doesn't display anything
+        let res = parse_args(&[String::from("--distinct-precision"), String::from("4")]);
+        assert_eq!(res.display_options, DisplayOptions::default_options());
+    }
+
+    #[test]
+    fn test_parse_word_mode() {
+        let res = parse_args(&[String::from("-w")]);
+        assert_eq!(res.count_options.word_mode, WordMode::Whitespace);
+        let res = parse_args(&[String::from("--word-mode=uax29")]);
+        assert_eq!(res.count_options.word_mode, WordMode::Uax29);
+        let res = parse_args(&[String::from("--word-mode"), String::from("whitespace")]);
+        assert_eq!(res.count_options.word_mode, WordMode::Whitespace);
+        assert_eq!(res.count_options.tab_width, 8);
+        let res = parse_args(&[String::from("--tab-width=4"), String::from("--columns")]);
+        assert_eq!(res.count_options.tab_width, 4);
+        assert_eq!(res.display_options, DisplayOptions::with_columns_only());
+    }
+
+    #[test]
+    fn test_parse_whitespace() {
+        let res = parse_args(&[String::from("-w")]);
+        assert_eq!(res.count_options.whitespace, WhitespaceMode::Unicode);
+        let res = parse_args(&[String::from("--whitespace=posix")]);
+        assert_eq!(res.count_options.whitespace, WhitespaceMode::Posix);
+        let res = parse_args(&[String::from("--whitespace"), String::from("ascii")]);
+        assert_eq!(res.count_options.whitespace, WhitespaceMode::Ascii);
+    }
+
+    #[test]
+    fn test_parse_line_terminator() {
+        let res = parse_args(&[String::from("-l")]);
+        assert_eq!(res.count_options.line_terminator, LineTerminator::Lf);
+        let res = parse_args(&[String::from("--line-terminator=crlf")]);
+        assert_eq!(res.count_options.line_terminator, LineTerminator::Crlf);
+        let res = parse_args(&[String::from("--line-terminator"), String::from("any")]);
+        assert_eq!(res.count_options.line_terminator, LineTerminator::Any);
+        let res = parse_args(&[String::from("--line-terminator=cr")]);
+        assert_eq!(res.count_options.line_terminator, LineTerminator::Cr);
     }
 }
@@ -0,0 +1,724 @@
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::count_options::CountOptions;
+use crate::display_options::DisplayOptions;
+use crate::extras::counts_and_extras_for_reader;
+use crate::file_result::FileResult;
+
+/// A string literal of a `Language`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Quote {
+    open: &'static str,
+    close: &'static str,
+    /// whether the string can continue onto the next line
+    multiline: bool,
+    /// whether a `\` escapes the character after it, so it can't close the
+    /// string
+    escape: bool,
+}
+
+const fn quote(open: &'static str, close: &'static str, multiline: bool) -> Quote {
+    Quote {
+        open,
+        close,
+        multiline,
+        escape: true,
+    }
+}
+
+/// A string literal without escapes, like a Rust raw string.
+const fn raw_quote(open: &'static str, close: &'static str, multiline: bool) -> Quote {
+    Quote {
+        open,
+        close,
+        multiline,
+        escape: false,
+    }
+}
+
+/// The comment and string syntax of a programming language, enough to
+/// tell code lines from comment lines.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Language {
+    pub name: &'static str,
+    /// file name extensions, without the `.`
+    extensions: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comments: &'static [(&'static str, &'static str)],
+    /// whether block comments can be nested
+    nested: bool,
+    /// string literals, which can contain comment markers, checked in order
+    /// so longer openings have to come first
+    quotes: &'static [Quote],
+    /// whether `'` starts a Rust char literal like `'"'` or `'\n'`, or
+    /// otherwise a lifetime or label
+    char_literals: bool,
+}
+
+const C_QUOTES: &[Quote] = &[quote("\"", "\"", false), quote("'", "'", false)];
+
+/// The languages detected by `language_for_path`.
+pub const LANGUAGES: &[Language] = &[
+    Language {
+        name: "C",
+        extensions: &["c", "h"],
+        line_comments: &["//"],
+        block_comments: &[("/*", "*/")],
+        nested: false,
+        quotes: C_QUOTES,
+        char_literals: false,
+    },
+    Language {
+        name: "C++",
+        extensions: &["cc", "cpp", "cxx", "hh", "hpp", "hxx"],
+        line_comments: &["//"],
+        block_comments: &[("/*", "*/")],
+        nested: false,
+        quotes: C_QUOTES,
+        char_literals: false,
+    },
+    Language {
+        name: "CSS",
+        extensions: &["css"],
+        line_comments: &[],
+        block_comments: &[("/*", "*/")],
+        nested: false,
+        quotes: C_QUOTES,
+        char_literals: false,
+    },
+    Language {
+        name: "Go",
+        extensions: &["go"],
+        line_comments: &["//"],
+        block_comments: &[("/*", "*/")],
+        nested: false,
+        quotes: &[
+            quote("\"", "\"", false),
+            quote("'", "'", false),
+            raw_quote("`", "`", true),
+        ],
+        char_literals: false,
+    },
+    Language {
+        name: "HTML",
+        extensions: &["htm", "html"],
+        line_comments: &[],
+        block_comments: &[("<!--", "-->")],
+        nested: false,
+        quotes: &[],
+        char_literals: false,
+    },
+    Language {
+        name: "Java",
+        extensions: &["java"],
+        line_comments: &["//"],
+        block_comments: &[("/*", "*/")],
+        nested: false,
+        quotes: &[
+            quote("\"\"\"", "\"\"\"", true),
+            quote("\"", "\"", false),
+            quote("'", "'", false),
+        ],
+        char_literals: false,
+    },
+    Language {
+        name: "JavaScript",
+        extensions: &["cjs", "js", "jsx", "mjs"],
+        line_comments: &["//"],
+        block_comments: &[("/*", "*/")],
+        nested: false,
+        quotes: &[
+            quote("\"", "\"", false),
+            quote("'", "'", false),
+            quote("`", "`", true),
+        ],
+        char_literals: false,
+    },
+    Language {
+        name: "Python",
+        extensions: &["py", "pyi"],
+        line_comments: &["#"],
+        block_comments: &[],
+        nested: false,
+        quotes: &[
+            quote("\"\"\"", "\"\"\"", true),
+            quote("'''", "'''", true),
+            quote("\"", "\"", false),
+            quote("'", "'", false),
+        ],
+        char_literals: false,
+    },
+    Language {
+        name: "Rust",
+        extensions: &["rs"],
+        line_comments: &["//"],
+        block_comments: &[("/*", "*/")],
+        nested: true,
+        quotes: &[
+            raw_quote("r##\"", "\"##", true),
+            raw_quote("r#\"", "\"#", true),
+            raw_quote("r\"", "\"", true),
+            quote("\"", "\"", true),
+        ],
+        char_literals: true,
+    },
+    Language {
+        name: "Shell",
+        extensions: &["bash", "sh", "zsh"],
+        line_comments: &["#"],
+        block_comments: &[],
+        nested: false,
+        quotes: &[quote("\"", "\"", true), raw_quote("'", "'", true)],
+        char_literals: false,
+    },
+    Language {
+        name: "SQL",
+        extensions: &["sql"],
+        line_comments: &["--"],
+        block_comments: &[("/*", "*/")],
+        nested: false,
+        quotes: &[raw_quote("'", "'", true)],
+        char_literals: false,
+    },
+    Language {
+        name: "TOML",
+        extensions: &["toml"],
+        line_comments: &["#"],
+        block_comments: &[],
+        nested: false,
+        quotes: &[
+            quote("\"\"\"", "\"\"\"", true),
+            raw_quote("'''", "'''", true),
+            quote("\"", "\"", false),
+            raw_quote("'", "'", false),
+        ],
+        char_literals: false,
+    },
+    Language {
+        name: "TypeScript",
+        extensions: &["cts", "mts", "ts", "tsx"],
+        line_comments: &["//"],
+        block_comments: &[("/*", "*/")],
+        nested: false,
+        quotes: &[
+            quote("\"", "\"", false),
+            quote("'", "'", false),
+            quote("`", "`", true),
+        ],
+        char_literals: false,
+    },
+    Language {
+        name: "YAML",
+        extensions: &["yaml", "yml"],
+        line_comments: &["#"],
+        block_comments: &[],
+        nested: false,
+        quotes: &[quote("\"", "\"", false), raw_quote("'", "'", false)],
+        char_literals: false,
+    },
+];
+
+/// Detect the language of the file at `path` from its extension.
+pub fn language_for_path(path: &Path) -> Option<&'static Language> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|language| language.extensions.contains(&extension.as_str()))
+}
+
+/// The number of code, comment and blank lines in a file. A line with any
+/// code on it is a code line, even if it also has a comment.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct CodeStats {
+    pub code: usize,
+    pub comments: usize,
+    pub blanks: usize,
+}
+
+impl CodeStats {
+    pub fn new(code: usize, comments: usize, blanks: usize) -> Self {
+        Self {
+            code,
+            comments,
+            blanks,
+        }
+    }
+
+    /// Add the counts in `other` to `self`.
+    pub fn add_mut(&mut self, other: &Self) {
+        self.code += other.code;
+        self.comments += other.comments;
+        self.blanks += other.blanks;
+    }
+}
+
+/// The longest Rust char literal, `'\u{10FFFF}'`, in bytes.
+const MAX_CHAR_LITERAL_LEN: usize = 12;
+
+/// How many bytes `CodeCounter` needs to see ahead of its position to tell
+/// which marker or character starts there, which is enough for the longest
+/// char literal as every comment and string marker is shorter.
+const LOOKAHEAD: usize = MAX_CHAR_LITERAL_LEN;
+
+/// Classifies the lines of a file in `language` as code, comment or blank,
+/// incrementally from a stream of byte chunks like `Counter`. Bytes are
+/// classified a character or marker at a time, so only the end of a chunk
+/// that is too short to tell which marker it starts is kept for the next
+/// one. Block comments and strings can span lines, and comment markers
+/// inside strings or char literals are not comments.
+#[derive(Debug, Clone)]
+pub struct CodeCounter {
+    language: &'static Language,
+    stats: CodeStats,
+    /// the end of the previous chunk, within `LOOKAHEAD` bytes of its end
+    pending: Vec<u8>,
+    /// whether the current line has any bytes
+    line_started: bool,
+    /// whether the current line so far has any code
+    code: bool,
+    /// whether the current line so far has any comment
+    comment: bool,
+    /// whether the rest of the current line is a line comment
+    line_comment: bool,
+    /// the number of block comments the current position is inside
+    comment_depth: usize,
+    /// the block comment the current position is inside
+    block_comment: Option<&'static (&'static str, &'static str)>,
+    /// the string the current position is inside
+    quote: Option<&'static Quote>,
+}
+
+impl CodeCounter {
+    pub fn new(language: &'static Language) -> Self {
+        Self {
+            language,
+            stats: CodeStats::default(),
+            pending: Vec::new(),
+            line_started: false,
+            code: false,
+            comment: false,
+            line_comment: false,
+            comment_depth: 0,
+            block_comment: None,
+            quote: None,
+        }
+    }
+
+    /// Classify the next chunk of the input.
+    pub fn update(&mut self, mut bytes: &[u8]) {
+        // the bytes kept from the previous chunk are classified along with
+        // just enough of this one to look ahead of them
+        if !self.pending.is_empty() {
+            let mut pending = std::mem::take(&mut self.pending);
+            let carried = pending.len();
+            pending.extend_from_slice(&bytes[..bytes.len().min(LOOKAHEAD)]);
+            let end = self.scan(&pending, carried, false);
+            if end < carried {
+                // all of `bytes` is in `pending`, and still too short
+                pending.drain(..end);
+                self.pending = pending;
+                return;
+            }
+            bytes = &bytes[end - carried..];
+            pending.clear();
+            self.pending = pending;
+        }
+        let end = self.scan(bytes, bytes.len(), false);
+        self.pending.extend_from_slice(&bytes[end..]);
+    }
+
+    /// Finish classifying and return the counts for all chunks seen. A final
+    /// line without a trailing newline is still a line.
+    pub fn finish(mut self) -> CodeStats {
+        let pending = std::mem::take(&mut self.pending);
+        self.scan(&pending, pending.len(), true);
+        if self.line_started {
+            self.end_line();
+        }
+        self.stats
+    }
+
+    /// Classify the characters and markers of `bytes` that start before
+    /// `end`, stopping at the first one with fewer than `LOOKAHEAD` bytes
+    /// after it unless `bytes` is the end of the input. Returns where the
+    /// classified bytes end.
+    fn scan(&mut self, bytes: &[u8], end: usize, at_end: bool) -> usize {
+        let mut i = 0;
+        while i < end && (at_end || bytes.len() - i >= LOOKAHEAD) {
+            i += self.step(&bytes[i..]);
+        }
+        i
+    }
+
+    /// Classify the character or marker `rest` starts with, returning its
+    /// length in bytes. The comment and string markers are all ASCII, so
+    /// they can be matched a byte at a time without splitting characters.
+    fn step(&mut self, rest: &[u8]) -> usize {
+        let language = self.language;
+        if rest[0] == b'\n' {
+            self.end_line();
+            return 1;
+        }
+        self.line_started = true;
+        if self.line_comment {
+            return rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+        }
+        if let Some(len) = whitespace_len(rest) {
+            return len;
+        }
+
+        if let Some(quote) = self.quote {
+            self.code = true;
+            if quote.escape && rest[0] == b'\\' {
+                // an escaped newline still ends the line
+                return if rest.get(1).is_some_and(|&b| b != b'\n') {
+                    2
+                } else {
+                    1
+                };
+            }
+            if rest.starts_with(quote.close.as_bytes()) {
+                self.quote = None;
+                return quote.close.len();
+            }
+            return 1;
+        }
+        if let Some(&(open, close)) = self.block_comment {
+            self.comment = true;
+            if language.nested && rest.starts_with(open.as_bytes()) {
+                self.comment_depth += 1;
+                return open.len();
+            }
+            if rest.starts_with(close.as_bytes()) {
+                self.comment_depth -= 1;
+                if self.comment_depth == 0 {
+                    self.block_comment = None;
+                }
+                return close.len();
+            }
+            return 1;
+        }
+
+        if starts_with_any(rest, language.line_comments).is_some() {
+            self.comment = true;
+            self.line_comment = true;
+            return 1;
+        }
+        if let Some(block) = language
+            .block_comments
+            .iter()
+            .find(|(open, _)| rest.starts_with(open.as_bytes()))
+        {
+            self.comment = true;
+            self.block_comment = Some(block);
+            self.comment_depth = 1;
+            return block.0.len();
+        }
+        self.code = true;
+        if let Some(len) = language
+            .char_literals
+            .then(|| char_literal_len(rest))
+            .flatten()
+        {
+            return len;
+        }
+        if let Some(quote) = language
+            .quotes
+            .iter()
+            .find(|quote| rest.starts_with(quote.open.as_bytes()))
+        {
+            self.quote = Some(quote);
+            return quote.open.len();
+        }
+        1
+    }
+
+    /// Count the line that just ended and start a new one. A line with
+    /// nothing but whitespace is blank even inside a comment or string.
+    fn end_line(&mut self) {
+        if self.code {
+            self.stats.code += 1;
+        } else if self.comment {
+            self.stats.comments += 1;
+        } else {
+            self.stats.blanks += 1;
+        }
+        self.line_started = false;
+        self.code = false;
+        self.comment = false;
+        self.line_comment = false;
+        // an unterminated single line string ends with its line
+        if self.quote.is_some_and(|quote| !quote.multiline) {
+            self.quote = None;
+        }
+    }
+}
+
+/// Returns the first of `markers` that `bytes` starts with.
+fn starts_with_any(bytes: &[u8], markers: &[&'static str]) -> Option<&'static str> {
+    markers
+        .iter()
+        .copied()
+        .find(|marker| bytes.starts_with(marker.as_bytes()))
+}
+
+/// The length of the whitespace character `bytes` starts with, or `None` if
+/// it doesn't start with one.
+fn whitespace_len(bytes: &[u8]) -> Option<usize> {
+    let len = utf8_len(bytes[0]);
+    let c = std::str::from_utf8(bytes.get(..len)?)
+        .ok()?
+        .chars()
+        .next()?;
+    c.is_whitespace().then_some(len)
+}
+
+/// The length of the Rust char literal `bytes` starts with, or `None` if it
+/// doesn't start with one, e.g. it starts with a lifetime like `'a`.
+fn char_literal_len(bytes: &[u8]) -> Option<usize> {
+    if bytes.first() != Some(&b'\'') {
+        return None;
+    }
+    let len = match *bytes.get(1)? {
+        // an escape ends at the first `'` after the escaped character
+        b'\\' => {
+            let escape = bytes.get(3..bytes.len().min(MAX_CHAR_LITERAL_LEN))?;
+            3 + escape
+                .iter()
+                .take_while(|&&b| b != b'\n')
+                .position(|&b| b == b'\'')?
+        }
+        b => 1 + utf8_len(b),
+    };
+    (bytes.get(len) == Some(&b'\'')).then_some(len + 1)
+}
+
+/// The length of the UTF-8 encoded character starting with the byte `b`,
+/// or 1 if `b` can't start one.
+fn utf8_len(b: u8) -> usize {
+    match b {
+        0xf0.. => 4,
+        0xe0.. => 3,
+        0xc0.. => 2,
+        _ => 1,
+    }
+}
+
+/// Compute the counts needed to display `options` and the code statistics
+/// in `language` in a single pass over everything read from `reader`, with
+/// the default `CountOptions`, see `counts_and_extras_for_reader`.
+pub fn counts_and_code_stats_for_reader<R: Read>(
+    reader: &mut R,
+    options: &DisplayOptions,
+    language: &'static Language,
+) -> io::Result<(FileResult, CodeStats)> {
+    let (result, extras) = counts_and_extras_for_reader(
+        reader,
+        options,
+        &CountOptions::default(),
+        Some(language),
+        None,
+    )?;
+    let stats = extras
+        .code_stats
+        .map(|(_, stats)| stats)
+        .unwrap_or_default();
+    Ok((result, stats))
+}
+
+/// The number of files and their combined `CodeStats` for a language.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct LanguageTotal {
+    pub files: usize,
+    pub stats: CodeStats,
+}
+
+/// The code statistics of every file added, summed by language and sorted
+/// by language name.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct LanguageTotals {
+    totals: BTreeMap<&'static str, LanguageTotal>,
+}
+
+impl LanguageTotals {
+    /// Add the `stats` of a file in `language`.
+    pub fn add(&mut self, language: &Language, stats: &CodeStats) {
+        let total = self.totals.entry(language.name).or_default();
+        total.files += 1;
+        total.stats.add_mut(stats);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.totals.is_empty()
+    }
+
+    /// The names of the languages with their totals, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &LanguageTotal)> {
+        self.totals.iter().map(|(name, total)| (*name, total))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn language(name: &str) -> &'static Language {
+        LANGUAGES
+            .iter()
+            .find(|language| language.name == name)
+            .unwrap()
+    }
+
+    /// Classify the lines of `s` in the language called `name`, feeding it
+    /// to a `CodeCounter` in chunks of `size` bytes.
+    fn stats_in_chunks(name: &str, s: &str, size: usize) -> CodeStats {
+        let mut counter = CodeCounter::new(language(name));
+        for chunk in s.as_bytes().chunks(size) {
+            counter.update(chunk);
+        }
+        counter.finish()
+    }
+
+    #[test]
+    fn test_language_for_path() {
+        assert_eq!(
+            language_for_path(Path::new("src/main.rs")).unwrap().name,
+            "Rust"
+        );
+        assert_eq!(
+            language_for_path(Path::new("a/b.PY")).unwrap().name,
+            "Python"
+        );
+        assert_eq!(language_for_path(Path::new("x.hpp")).unwrap().name, "C++");
+        assert!(language_for_path(Path::new("README")).is_none());
+        assert!(language_for_path(Path::new("notes.txt")).is_none());
+    }
+
+    #[test]
+    fn test_rust() {
+        let s = concat!(
+            "// a comment\n",
+            "fn main() { // trailing comment\n",
+            "\n",
+            "    /* block\n",
+            "       /* nested */\n",
+            "       still a comment */\n",
+            "    let s = \"not // a comment\";\n",
+            "    let t = \"/* nor\n",
+            "    this */\";\n",
+            "    /* */ x();\n",
+            "   \t\n",
+            "}"
+        );
+        for size in 1..=s.len() {
+            assert_eq!(
+                stats_in_chunks("Rust", s, size),
+                CodeStats::new(6, 4, 2),
+                "chunk size {size}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_rust_literals() {
+        let s = concat!(
+            "let q = '\"';\n",
+            "// a comment\n",
+            "let p = r\"C:\\\";\n",
+            "// a comment\n",
+            "let h = r#\"a \"quoted\" // string\"#;\n",
+            "fn f<'a>(s: &'a str) -> char { '\\'' }\n",
+            "'outer: loop { b'\"'; '\\u{22}'; 'é'; }\n",
+            "/* a comment */\n",
+        );
+        for size in 1..=s.len() {
+            assert_eq!(
+                stats_in_chunks("Rust", s, size),
+                CodeStats::new(5, 3, 0),
+                "chunk size {size}"
+            );
+        }
+        assert_eq!(char_literal_len(b"'a' "), Some(3));
+        assert_eq!(char_literal_len("'é'".as_bytes()), Some(4));
+        assert_eq!(char_literal_len(b"'\\n'"), Some(4));
+        assert_eq!(char_literal_len(b"'\\''"), Some(4));
+        assert_eq!(char_literal_len(b"'a>"), None);
+        assert_eq!(char_literal_len(b"'static"), None);
+    }
+
+    #[test]
+    fn test_raw_quotes() {
+        // a backslash doesn't escape in shell single quotes or SQL strings
+        let s = "echo 'C:\\'\n# a comment\n";
+        assert_eq!(stats_in_chunks("Shell", s, 3), CodeStats::new(1, 1, 0));
+        let s = "SELECT '\\';\n-- a comment\n";
+        assert_eq!(stats_in_chunks("SQL", s, 3), CodeStats::new(1, 1, 0));
+    }
+
+    #[test]
+    fn test_c() {
+        // C block comments don't nest
+        let s = "/* a /* b */\nint c = '\"'; /* d\n*/\nchar *e = \"\\\"//\";\n";
+        assert_eq!(stats_in_chunks("C", s, 4), CodeStats::new(2, 2, 0));
+    }
+
+    #[test]
+    fn test_python() {
+        let s = "# comment\nx = 1  # trailing\n\"\"\"doc\n# still a string\n\"\"\"\ny = '#'\n\n";
+        assert_eq!(stats_in_chunks("Python", s, 3), CodeStats::new(5, 1, 1));
+    }
+
+    #[test]
+    fn test_long_lines() {
+        // only the end of each chunk is kept, however long the line is
+        let s = "let s = \"a\"; /* b */ ".repeat(10_000);
+        let mut counter = CodeCounter::new(language("Rust"));
+        for chunk in s.as_bytes().chunks(1000) {
+            counter.update(chunk);
+            assert!(counter.pending.len() < LOOKAHEAD);
+        }
+        assert_eq!(counter.finish(), CodeStats::new(1, 0, 0));
+    }
+
+    #[test]
+    fn test_unicode_whitespace() {
+        let s = "\u{3000}\r\n\u{a0}// a comment\n\u{2003}x\n";
+        for size in 1..=s.len() {
+            assert_eq!(
+                stats_in_chunks("Rust", s, size),
+                CodeStats::new(1, 1, 1),
+                "chunk size {size}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_counts_and_code_stats() {
+        let s = "// one\nfn two() {}\n";
+        let (result, stats) = counts_and_code_stats_for_reader(
+            &mut s.as_bytes(),
+            &DisplayOptions::default_options(),
+            language("Rust"),
+        )
+        .unwrap();
+        assert_eq!(result.lines, 2);
+        assert_eq!(result.words, 5);
+        assert_eq!(stats, CodeStats::new(1, 1, 0));
+    }
+
+    #[test]
+    fn test_language_totals() {
+        let mut totals = LanguageTotals::default();
+        assert!(totals.is_empty());
+        totals.add(language("Rust"), &CodeStats::new(1, 2, 3));
+        totals.add(language("C"), &CodeStats::new(1, 0, 0));
+        totals.add(language("Rust"), &CodeStats::new(4, 5, 6));
+        let names: Vec<_> = totals.iter().map(|(name, _)| name).collect();
+        assert_eq!(names, ["C", "Rust"]);
+        let (_, rust) = totals.iter().nth(1).unwrap();
+        assert_eq!(rust.files, 2);
+        assert_eq!(rust.stats, CodeStats::new(5, 7, 9));
+    }
+}
@@ -6,13 +6,71 @@ pub const HELP: &str = concat!(
     "Print newline, word, and byte counts for each FILE, and a total \n\
     line if more than one FILE is specified. A word is a nonempty \n\
     sequence of non whitespace delimited by whitespace characters \n\
-    or by start or end of input.\n\n\
+    or by start or end of input, unless --word-mode=uax29 is given.\n\n\
     If no FILE is provided, or when FILE is -, read standard input.\n\n\
-    The options below control which counts are printed, always in the
-    order: newline, word, character, byte. \n",
+    The options below control which counts are printed, always in the \n\
+    order: newline, word, character, byte, maximum line length, blank \n\
+    line, non-blank line, distinct words, grapheme, column, line \n\
+    ending. A blank line only contains whitespace.\n",
     "  -c, --bytes          print the byte counts\n",
     "  -m, --chars          print the character counts\n",
     "  -l, --lines          print the newline counts\n",
     "  -w, --words          print the word counts\n",
+    "  -L, --max-line-length  print the maximum display width\n",
+    "      --blank-lines    print the blank line counts\n",
+    "      --non-blank-lines  print the non-blank line counts\n",
+    "      --distinct-words  print an estimate of the number of distinct words\n",
+    "                         followed by its error bound\n",
+    "      --graphemes      print the counts of user perceived characters, i.e.\n",
+    "                         extended grapheme clusters\n",
+    "      --columns        print the total display width of the lines in\n",
+    "                         terminal columns\n",
+    "      --line-endings   print the counts of lines ending in a lone LF, a\n",
+    "                         CRLF, a lone CR, and a Unicode NEL, LS or PS\n",
+    "      --distinct-precision=P  estimate distinct words with 2^P bytes of\n",
+    "                         memory, P is from 4 to 18 and 12 by default\n",
+    "      --tab-width=N    expand tabs to the next multiple of N columns for\n",
+    "                         --columns and -L, 8 by default\n",
+    "      --whitespace=SET  separate words at SET whitespace, which can be:\n",
+    "                         unicode (default), ascii, or posix as in the C\n",
+    "                         locale, where no-break spaces are not whitespace\n",
+    "      --word-mode=MODE  split words at whitespace, or with MODE uax29 at\n",
+    "                         Unicode word boundaries, only counting those\n",
+    "                         containing letters or digits\n",
+    "      --line-terminator=END  end lines for the newline, blank and\n",
+    "                         non-blank line counts at END, which can be: lf\n",
+    "                         (default), crlf, cr, or any of the line endings\n",
+    "      --files0-from=F  read input from the files specified by\n",
+    "                         NUL-terminated names in file F;\n",
+    "                         If F is - then read names from standard input\n",
+    "      --total=WHEN     when to print a line with total counts;\n",
+    "                         WHEN can be: auto, always, only, never\n",
+    "      --format=FORMAT  print counts as FORMAT, which can be: text, json,\n",
+    "                         csv, tsv\n",
+    "      --code-stats     also print the code, comment and blank lines of the\n",
+    "                         files in each language detected by extension\n",
+    "      --word-freq[=N]  also print the N most frequent words, 10 by default\n",
+    "      --fold-case      count words that only differ in case as one word\n",
+    "      --strip-punctuation  ignore punctuation at the start and end of words\n",
+    "      --min-word-length=N  only count words of at least N characters in\n",
+    "                         the --word-freq report\n",
+    "  -r, --recursive      count the files in directories and their subdirectories\n",
+    "      --follow-symlinks  follow symbolic links while walking directories\n",
+    "      --max-depth=N    descend at most N levels into directories\n",
+    "      --hidden         include hidden files and directories, other than .git\n",
+    "      --one-file-system  don't descend into directories on other file systems\n",
+    "      --no-ignore      don't skip files ignored by .gitignore, .ignore and\n",
+    "                         .git/info/exclude files, or .git directories, while\n",
+    "                         walking directories\n",
+    "      --include=GLOB   only count files whose path matches GLOB\n",
+    "      --exclude=GLOB   don't count files or walk directories matching GLOB;\n",
+    "                         both can be repeated, and a GLOB without a / is\n",
+    "                         matched against the file name\n",
+    "  -j, --jobs=N         count up to N files in parallel\n",
+    "      --split-files    split each regular file into byte ranges counted\n",
+    "                         in parallel using the threads set by --jobs, except\n",
+    "                         files counted for --code-stats or --word-freq\n",
+    "      --mmap           memory map regular files instead of reading them; a file\n",
+    "                         truncated while it is counted can crash wcrs\n",
     "  -h, --help           display this help and exit\n"
 );
@@ -0,0 +1,150 @@
+use crate::width::TAB_WIDTH;
+
+/// How the input is split into words.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WordMode {
+    /// a word is a run of characters that are not whitespace
+    #[default]
+    Whitespace,
+    /// words are found with the Unicode word boundary rules of UAX #29, and
+    /// only segments containing a letter or digit are words
+    Uax29,
+}
+
+/// Which characters separate words and make up blank lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WhitespaceMode {
+    /// characters with the Unicode `White_Space` property
+    #[default]
+    Unicode,
+    /// the ASCII whitespace of the WHATWG Infra standard: space, tab, line
+    /// feed, form feed and carriage return
+    Ascii,
+    /// the whitespace of the POSIX C locale, the ASCII whitespace and
+    /// vertical tab, as in GNU wc with `LC_ALL=C`
+    Posix,
+}
+
+impl WhitespaceMode {
+    /// Returns `true` if `c` is whitespace in this mode.
+    pub fn is_whitespace(self, c: char) -> bool {
+        match self {
+            WhitespaceMode::Unicode => c.is_whitespace(),
+            WhitespaceMode::Ascii => c.is_ascii_whitespace(),
+            WhitespaceMode::Posix => c.is_ascii_whitespace() || c == '\x0b',
+        }
+    }
+}
+
+/// Which line terminators end a line when counting lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineTerminator {
+    /// every line feed, including the one of a CRLF, as in GNU wc
+    #[default]
+    Lf,
+    /// only a carriage return followed by a line feed
+    Crlf,
+    /// every carriage return, including the one of a CRLF
+    Cr,
+    /// a line feed, a CRLF, a carriage return that is not followed by a
+    /// line feed, or a Unicode next line, line separator or paragraph
+    /// separator
+    Any,
+}
+
+impl LineTerminator {
+    /// Returns `true` if `c` ends a line in this mode, where `after_cr` is
+    /// whether the character before `c` was a carriage return. A CRLF ends
+    /// a line at its carriage return with `LineTerminator::Any`.
+    pub fn ends_line(self, c: char, after_cr: bool) -> bool {
+        match self {
+            LineTerminator::Lf => c == '\n',
+            LineTerminator::Crlf => c == '\n' && after_cr,
+            LineTerminator::Cr => c == '\r',
+            LineTerminator::Any => match c {
+                '\n' => !after_cr,
+                '\r' | '\u{85}' | '\u{2028}' | '\u{2029}' => true,
+                _ => false,
+            },
+        }
+    }
+}
+
+/// Options that change how the input is counted, as opposed to
+/// `DisplayOptions` which choose the counts that are displayed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CountOptions {
+    pub word_mode: WordMode,
+    pub whitespace: WhitespaceMode,
+    /// the distance between tab stops when computing display widths
+    pub tab_width: usize,
+    pub line_terminator: LineTerminator,
+}
+
+impl Default for CountOptions {
+    fn default() -> Self {
+        Self {
+            word_mode: WordMode::default(),
+            whitespace: WhitespaceMode::default(),
+            tab_width: TAB_WIDTH,
+            line_terminator: LineTerminator::default(),
+        }
+    }
+}
+
+impl CountOptions {
+    /// Returns `true` if the input can be split into byte ranges that are
+    /// counted separately, see `PartialResult`, which only finds words
+    /// delimited by Unicode whitespace and lines ended by line feeds.
+    pub fn splittable(&self) -> bool {
+        self.word_mode == WordMode::Whitespace
+            && self.whitespace == WhitespaceMode::Unicode
+            && self.line_terminator == LineTerminator::Lf
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_whitespace() {
+        let modes = [
+            WhitespaceMode::Unicode,
+            WhitespaceMode::Ascii,
+            WhitespaceMode::Posix,
+        ];
+        for mode in modes {
+            for c in [' ', '\t', '\n', '\r', '\x0c'] {
+                assert!(mode.is_whitespace(c));
+            }
+            assert!(!mode.is_whitespace('a'));
+        }
+        assert_eq!(
+            modes.map(|mode| mode.is_whitespace('\x0b')),
+            [true, false, true]
+        );
+        for c in ['\u{a0}', '\u{3000}', '\u{85}'] {
+            assert_eq!(
+                modes.map(|mode| mode.is_whitespace(c)),
+                [true, false, false]
+            );
+        }
+    }
+
+    #[test]
+    fn test_ends_line() {
+        let modes = [
+            LineTerminator::Lf,
+            LineTerminator::Crlf,
+            LineTerminator::Cr,
+            LineTerminator::Any,
+        ];
+        let ends = |c, after_cr| modes.map(|mode| mode.ends_line(c, after_cr));
+        assert_eq!(ends('\n', false), [true, false, false, true]);
+        assert_eq!(ends('\n', true), [true, true, false, false]);
+        assert_eq!(ends('\r', false), [false, false, true, true]);
+        assert_eq!(ends('\u{2028}', false), [false, false, false, true]);
+        assert_eq!(ends('a', true), [false; 4]);
+    }
+}
@@ -0,0 +1,677 @@
+use std::io::{self, Read};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::count_options::{CountOptions, LineTerminator, WhitespaceMode, WordMode};
+use crate::display_options::DisplayOptions;
+use crate::distinct::{Sketch, WordHash};
+use crate::file_result::FileResult;
+use crate::newlines::count_newlines;
+use crate::width::ColumnCounter;
+
+/// Size in bytes of the buffer used when reading input in chunks.
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Computes line, word, character, and byte counts, the maximum line
+/// length, and blank and non-blank lines incrementally from a stream of
+/// byte chunks, so the input never has to be held in memory at once. Words
+/// and multi-byte characters that are split across chunks are counted
+/// exactly once.
+///
+/// Input does not need to be valid UTF-8. Bytes that are not part of a
+/// valid UTF-8 sequence are counted as bytes but not as characters, and
+/// are treated as non whitespace when counting words.
+///
+/// Grapheme clusters are only counted by a `Counter` created with
+/// `for_options` when they are displayed, since segmenting them is much
+/// slower than the other counts. Invalid bytes end a grapheme cluster.
+///
+/// With `WordMode::Uax29` words are found at Unicode word boundaries once
+/// the text that follows can no longer move them, see `final_segments_end`,
+/// so only the end of the current line is held in memory. Invalid bytes are
+/// then replaced with U+FFFD, which is neither a letter nor a digit.
+///
+/// Lines end at the `LineTerminator` of `for_options`, a line feed by
+/// default. The lines ending in each kind of line terminator are only
+/// counted when they are displayed, like grapheme clusters.
+#[derive(Debug, Clone, Default)]
+pub struct Counter {
+    result: FileResult,
+    /// whether the last character seen was part of a word
+    in_word: bool,
+    /// whether the last byte seen was a newline
+    ends_with_newline: bool,
+    /// leading bytes of a multi-byte character whose remaining bytes
+    /// are in the next chunk
+    pending: Vec<u8>,
+    /// the display width of the lines so far
+    columns: ColumnCounter,
+    /// whether the current line so far has any non whitespace
+    line_has_text: bool,
+    /// only count lines and bytes, without decoding characters
+    skip_decoding: bool,
+    /// don't compute the width of lines
+    skip_line_length: bool,
+    /// the sketch of the distinct words seen, if they are estimated
+    distinct: Option<Sketch>,
+    /// the hash of the current word so far, only computed for `distinct`
+    word: WordHash,
+    /// how the input is split into words
+    word_mode: WordMode,
+    /// which characters are whitespace
+    whitespace: WhitespaceMode,
+    /// the end of the current line whose words aren't counted yet, only
+    /// kept with `WordMode::Uax29`
+    line: String,
+    /// count grapheme clusters
+    count_graphemes: bool,
+    /// the last grapheme cluster seen, which may still be extended by the
+    /// characters that follow
+    cluster: String,
+    /// which line terminators end a line
+    line_terminator: LineTerminator,
+    /// count the lines ending in each kind of line terminator
+    count_line_endings: bool,
+    /// whether the last character seen was a carriage return
+    after_cr: bool,
+    /// whether the characters seen so far end with a line terminator, only
+    /// used when lines don't end at line feeds
+    line_ended: bool,
+}
+
+impl Counter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a `Counter` that only computes the counts needed to display
+    /// `options`. When only lines and bytes are displayed, newlines are
+    /// counted directly on the bytes and the other counts are left at 0,
+    /// unless lines end at anything but line feeds. Words and lines are
+    /// counted as set in `count_options`.
+    pub fn for_options(options: &DisplayOptions, count_options: &CountOptions) -> Self {
+        Self {
+            word_mode: count_options.word_mode,
+            whitespace: count_options.whitespace,
+            line_terminator: count_options.line_terminator,
+            count_line_endings: options.line_endings(),
+            skip_decoding: !options.needs_decoding()
+                && count_options.line_terminator == LineTerminator::Lf,
+            skip_line_length: !options.max_line_length() && !options.columns(),
+            columns: ColumnCounter::new(count_options.tab_width),
+            distinct: options.distinct_words().map(Sketch::new),
+            count_graphemes: options.graphemes(),
+            ..Self::default()
+        }
+    }
+
+    /// Count the next chunk of the input.
+    pub fn update(&mut self, mut bytes: &[u8]) {
+        self.result.bytes += bytes.len();
+        if let Some(&last) = bytes.last() {
+            self.ends_with_newline = last == b'\n';
+        }
+        if self.skip_decoding {
+            self.result.lines += count_newlines(bytes);
+            return;
+        }
+
+        // feed bytes one at a time to a character started in a previous
+        // chunk until it is either complete or known to be invalid
+        while !self.pending.is_empty() {
+            let Some((&b, rest)) = bytes.split_first() else {
+                return;
+            };
+            bytes = rest;
+            let mut pending = std::mem::take(&mut self.pending);
+            pending.push(b);
+            self.count_bytes(&pending);
+        }
+        self.count_bytes(bytes);
+        if self.word_mode == WordMode::Uax29 {
+            self.count_segments(final_segments_end(&self.line));
+        }
+    }
+
+    /// Read `reader` to the end in chunks of `CHUNK_SIZE` bytes and count
+    /// each chunk.
+    pub fn read_from<R: Read>(&mut self, reader: &mut R) -> io::Result<()> {
+        for_each_chunk(reader, |chunk| self.update(chunk))
+    }
+
+    /// Finish counting and return the counts for all chunks seen.
+    pub fn finish(mut self) -> FileResult {
+        // the input ended in the middle of a character
+        if !self.pending.is_empty() {
+            let pending = std::mem::take(&mut self.pending);
+            self.count_invalid(&pending);
+        }
+        self.end_clusters();
+        match self.word_mode {
+            WordMode::Whitespace => {
+                if let Some(sketch) = &mut self.distinct {
+                    if self.in_word {
+                        sketch.insert(&self.word);
+                    }
+                }
+            }
+            WordMode::Uax29 => self.count_segments(self.line.len()),
+        }
+        self.result.distinct_words = self.distinct.take();
+        self.columns.end_line();
+        self.result.max_line_length = self.columns.max();
+        self.result.columns = self.columns.total();
+        let terminated = match self.line_terminator {
+            LineTerminator::Lf => self.ends_with_newline,
+            _ => self.line_ended,
+        };
+        // a final line without a trailing terminator still counts as a line
+        if self.result.bytes > 0 && !terminated {
+            self.result.lines += 1;
+            if !self.skip_decoding {
+                self.count_line_kind();
+            }
+        }
+        self.result
+    }
+
+    /// Count the characters in `bytes`, keeping an incomplete character at
+    /// the end of `bytes` in `self.pending`.
+    fn count_bytes(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.utf8_chunks().peekable();
+        while let Some(chunk) = chunks.next() {
+            self.count_str(chunk.valid());
+            let invalid = chunk.invalid();
+            if invalid.is_empty() {
+                continue;
+            }
+            if chunks.peek().is_none() && is_incomplete_char(invalid) {
+                self.pending.extend_from_slice(invalid);
+            } else {
+                self.count_invalid(invalid);
+            }
+        }
+    }
+
+    /// Count the line that just ended as blank or non-blank and start a new
+    /// line.
+    fn count_line_kind(&mut self) {
+        if self.line_has_text {
+            self.result.non_blank_lines += 1;
+        } else {
+            self.result.blank_lines += 1;
+        }
+        self.line_has_text = false;
+    }
+
+    /// Count a sequence of bytes that is not valid UTF-8. It is not a
+    /// character but it is part of a word.
+    fn count_invalid(&mut self, bytes: &[u8]) {
+        self.line_has_text = true;
+        self.after_cr = false;
+        self.line_ended = false;
+        self.end_clusters();
+        if self.word_mode == WordMode::Uax29 {
+            self.line.push(char::REPLACEMENT_CHARACTER);
+            return;
+        }
+        if !self.in_word {
+            self.result.words += 1;
+        }
+        if self.distinct.is_some() {
+            self.word.extend(bytes);
+        }
+        self.in_word = true;
+    }
+
+    /// Count the character `c` towards the words split at whitespace.
+    fn count_word_char(&mut self, c: char, whitespace: bool) {
+        // a word starts at every non whitespace character that
+        // follows a whitespace character or the start of input
+        if !whitespace && !self.in_word {
+            self.result.words += 1;
+        }
+        if let Some(sketch) = &mut self.distinct {
+            if !whitespace {
+                let mut buf = [0; 4];
+                self.word.extend(c.encode_utf8(&mut buf).as_bytes());
+            } else if self.in_word {
+                sketch.insert(&std::mem::take(&mut self.word));
+            }
+        }
+        self.in_word = !whitespace;
+    }
+
+    /// Count `c` towards the kinds of line endings. A carriage return is
+    /// counted as a lone one until the line feed of a CRLF follows it.
+    fn count_line_ending(&mut self, c: char) {
+        match c {
+            '\n' if self.after_cr => {
+                self.result.cr_endings -= 1;
+                self.result.crlf_endings += 1;
+            }
+            '\n' => self.result.lf_endings += 1,
+            '\r' => self.result.cr_endings += 1,
+            '\u{85}' | '\u{2028}' | '\u{2029}' => self.result.unicode_endings += 1,
+            _ => {}
+        }
+    }
+
+    /// Count the words of the current line up to `end` by its Unicode word
+    /// boundaries and remove them from the line.
+    fn count_segments(&mut self, end: usize) {
+        for word in self.line[..end].unicode_words() {
+            self.result.words += 1;
+            if let Some(sketch) = &mut self.distinct {
+                sketch.insert(&WordHash::of(word.as_bytes()));
+            }
+        }
+        self.line.drain(..end);
+    }
+
+    /// Count the grapheme clusters completed by `s`, keeping the last one in
+    /// `self.cluster`. A boundary only depends on the character after it, so
+    /// every boundary before the last character seen is final.
+    fn count_clusters(&mut self, s: &str) {
+        self.cluster.push_str(s);
+        let mut clusters = 0;
+        let mut last = 0;
+        for (i, _) in self.cluster.grapheme_indices(true) {
+            clusters += 1;
+            last = i;
+        }
+        if clusters > 1 {
+            self.result.graphemes += clusters - 1;
+            self.cluster.drain(..last);
+        }
+    }
+
+    /// Count the last grapheme cluster seen as complete.
+    fn end_clusters(&mut self) {
+        if !self.cluster.is_empty() {
+            self.result.graphemes += 1;
+            self.cluster.clear();
+        }
+    }
+
+    fn count_str(&mut self, s: &str) {
+        if self.count_graphemes && !s.is_empty() {
+            self.count_clusters(s);
+        }
+        for c in s.chars() {
+            self.result.chars += 1;
+            let ends_line = self.line_terminator.ends_line(c, self.after_cr);
+            if ends_line {
+                self.result.lines += 1;
+            }
+            if self.count_line_endings {
+                self.count_line_ending(c);
+            }
+            if !self.skip_line_length {
+                self.columns.push(c);
+            }
+            let whitespace = self.whitespace.is_whitespace(c);
+            match self.word_mode {
+                WordMode::Whitespace => self.count_word_char(c, whitespace),
+                WordMode::Uax29 if c == '\n' => self.count_segments(self.line.len()),
+                WordMode::Uax29 => self.line.push(c),
+            }
+            if ends_line {
+                self.count_line_kind();
+            } else if !whitespace {
+                self.line_has_text = true;
+            }
+            // the line feed of a CRLF ends no line of its own with
+            // `LineTerminator::Any`, but the input still ends with a terminator
+            self.line_ended = ends_line
+                || (c == '\n' && self.after_cr && self.line_terminator == LineTerminator::Any);
+            self.after_cr = c == '\r';
+        }
+    }
+}
+
+/// The end of the Unicode word segments of `line` that the text following it
+/// can't change. Whether two segments are joined depends on at most the
+/// first letter or digit of the segment after them, as in `can't` or `3.14`,
+/// so only the last two segments may still change.
+pub(crate) fn final_segments_end(line: &str) -> usize {
+    line.split_word_bound_indices()
+        .rev()
+        .nth(1)
+        .map_or(0, |(i, _)| i)
+}
+
+/// Read `reader` to the end in chunks of at most `CHUNK_SIZE` bytes,
+/// calling `f` on each chunk.
+pub fn for_each_chunk<R: Read, F: FnMut(&[u8])>(reader: &mut R, mut f: F) -> io::Result<()> {
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => f(&buf[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Returns `true` if `bytes` is the start of a valid UTF-8 encoded
+/// character that is missing its final bytes.
+pub(crate) fn is_incomplete_char(bytes: &[u8]) -> bool {
+    std::str::from_utf8(bytes).is_err_and(|e| e.error_len().is_none())
+}
+
+/// Compute line, word, character, and byte counts for everything read
+/// from `reader` without loading it all into memory.
+pub fn counts_for_reader<R: Read>(reader: &mut R) -> io::Result<FileResult> {
+    let mut counter = Counter::new();
+    counter.read_from(reader)?;
+    Ok(counter.finish())
+}
+
+/// Compute the counts needed to display `options` for everything read
+/// from `reader`, see `Counter::for_options`.
+pub fn counts_for_reader_with_options<R: Read>(
+    reader: &mut R,
+    options: &DisplayOptions,
+    count_options: &CountOptions,
+) -> io::Result<FileResult> {
+    let mut counter = Counter::for_options(options, count_options);
+    counter.read_from(reader)?;
+    Ok(counter.finish())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::file_result::counts_for_file;
+
+    /// Count `s` by feeding it to a `Counter` in chunks of `size` bytes.
+    fn counts_in_chunks(s: &[u8], size: usize) -> FileResult {
+        let mut counter = Counter::new();
+        for chunk in s.chunks(size) {
+            counter.update(chunk);
+        }
+        counter.finish()
+    }
+
+    #[test]
+    fn test_split_chunks() {
+        let s = "héllo wörld\n日本語  テキスト\n\tlast line 🦀";
+        for size in 1..=s.len() {
+            assert_eq!(
+                counts_in_chunks(s.as_bytes(), size),
+                counts_for_file(s.as_bytes()),
+                "chunk size {size}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_word_across_chunks() {
+        let mut counter = Counter::new();
+        counter.update(b"one tw");
+        counter.update(b"o thr");
+        counter.update(b"ee\n");
+        assert_eq!(
+            counter.finish(),
+            FileResult {
+                max_line_length: 13,
+                non_blank_lines: 1,
+                columns: 13,
+                ..FileResult::new(1, 3, 14, 14)
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        // invalid bytes are part of the word they appear in, but have no
+        // display width
+        assert_eq!(
+            counts_in_chunks(b"abc\xffdef \xfe\n", 2),
+            FileResult {
+                max_line_length: 7,
+                non_blank_lines: 1,
+                columns: 7,
+                ..FileResult::new(1, 2, 8, 10)
+            }
+        );
+        // a truncated character followed by a valid one
+        assert_eq!(
+            counts_in_chunks(b"\xe2\x82 \xe2\x82\xac", 1),
+            FileResult {
+                max_line_length: 2,
+                non_blank_lines: 1,
+                columns: 2,
+                ..FileResult::new(1, 2, 2, 6)
+            }
+        );
+        // input ending in the middle of a character
+        assert_eq!(
+            counts_in_chunks(&"ab€".as_bytes()[..4], 3),
+            FileResult {
+                max_line_length: 2,
+                non_blank_lines: 1,
+                columns: 2,
+                ..FileResult::new(1, 1, 2, 4)
+            }
+        );
+        // Latin-1 encoded text
+        assert_eq!(
+            counts_in_chunks(b"caf\xe9 cr\xe8me\n", 4),
+            FileResult {
+                max_line_length: 8,
+                non_blank_lines: 1,
+                columns: 8,
+                ..FileResult::new(1, 2, 9, 11)
+            }
+        );
+    }
+
+    #[test]
+    fn test_counts_for_reader() {
+        let s = "a b c\nd e\n";
+        assert_eq!(
+            counts_for_reader(&mut s.as_bytes()).unwrap(),
+            FileResult {
+                max_line_length: 5,
+                non_blank_lines: 2,
+                columns: 8,
+                ..FileResult::new(2, 5, 10, 10)
+            }
+        );
+        assert_eq!(
+            counts_for_reader(&mut "".as_bytes()).unwrap(),
+            FileResult::default()
+        );
+    }
+
+    #[test]
+    fn test_for_options() {
+        let s = "héllo wörld\n日本語  テキスト\n\tlast line 🦀".as_bytes();
+        let count_options = CountOptions::default();
+        let options = DisplayOptions::new(true, false, false, true);
+        let mut counter = Counter::for_options(&options, &count_options);
+        for chunk in s.chunks(5) {
+            counter.update(chunk);
+        }
+        let full = counts_for_file(s);
+        assert_eq!(
+            counter.finish(),
+            FileResult::new(full.lines, 0, 0, full.bytes)
+        );
+
+        let options = DisplayOptions::with_words_only();
+        let result = counts_for_reader_with_options(&mut &s[..], &options, &count_options).unwrap();
+        assert_eq!(
+            result,
+            FileResult {
+                max_line_length: 0,
+                columns: 0,
+                ..full
+            }
+        );
+    }
+
+    #[test]
+    fn test_word_mode_uax29() {
+        let count_options = CountOptions {
+            word_mode: WordMode::Uax29,
+            ..CountOptions::default()
+        };
+        let words = |s: &[u8], size: usize| {
+            let mut counter =
+                Counter::for_options(&DisplayOptions::with_words_only(), &count_options);
+            for chunk in s.chunks(size) {
+                counter.update(chunk);
+            }
+            counter.finish().words
+        };
+        for size in [1, 2, 64] {
+            assert_eq!(words("don't\u{2014}stop".as_bytes(), size), 2);
+            assert_eq!(words("The quick (\"brown\") fox.\n".as_bytes(), size), 4);
+            // each ideograph is a word of its own
+            assert_eq!(words("日本語\nテキスト".as_bytes(), size), 4);
+            // punctuation alone is not a word
+            assert_eq!(words(b" -- ... !", size), 0);
+            // invalid bytes split words and numbers keep their decimal point
+            assert_eq!(words(b"caf\xe9 cr\xe8me\n3.14", size), 4);
+        }
+    }
+
+    #[test]
+    fn test_word_mode_uax29_long_line() {
+        let count_options = CountOptions {
+            word_mode: WordMode::Uax29,
+            ..CountOptions::default()
+        };
+        let options =
+            DisplayOptions::with_words_only().join(&DisplayOptions::with_distinct_words_only(8));
+        // words that are only joined once the text after them is seen
+        let s = "can't 3.14 a.b x\u{301}y\u{301} 🇫🇷🇩🇪 日本 \u{5d0}\"\u{5d1} ".repeat(2000);
+        let expected =
+            counts_for_reader_with_options(&mut s.as_bytes(), &options, &count_options).unwrap();
+        assert_eq!(expected.words, 7 * 2000);
+        for size in [1, 2, 3, 7, 64] {
+            let mut counter = Counter::for_options(&options, &count_options);
+            for chunk in s.as_bytes().chunks(size) {
+                counter.update(chunk);
+                assert!(counter.line.len() < 64, "chunk size {size}");
+            }
+            let result = counter.finish();
+            assert_eq!(result.words, expected.words, "chunk size {size}");
+            assert_eq!(
+                result.distinct_words, expected.distinct_words,
+                "chunk size {size}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_graphemes() {
+        let graphemes = |s: &[u8], size: usize| {
+            let options = DisplayOptions::with_graphemes_only();
+            let mut counter = Counter::for_options(&options, &CountOptions::default());
+            for chunk in s.chunks(size) {
+                counter.update(chunk);
+            }
+            counter.finish().graphemes
+        };
+        for size in [1, 2, 64] {
+            assert_eq!(graphemes(b"", size), 0);
+            // e followed by a combining acute accent
+            assert_eq!(graphemes("e\u{301}\n".as_bytes(), size), 2);
+            // a family emoji joined by zero width joiners
+            assert_eq!(
+                graphemes("👨\u{200d}👩\u{200d}👧\u{200d}👦".as_bytes(), size),
+                1
+            );
+            // a pair of regional indicators is a flag
+            assert_eq!(graphemes("🇫🇷🇩".as_bytes(), size), 2);
+            assert_eq!(graphemes(b"a\r\nb", size), 3);
+            assert_eq!(graphemes(b"e\xff\xcc\x81", size), 2);
+        }
+        // only counted when displayed
+        assert_eq!(counts_in_chunks("e\u{301}".as_bytes(), 1).graphemes, 0);
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let endings = |s: &[u8], size: usize| {
+            let options = DisplayOptions::with_line_endings_only();
+            let mut counter = Counter::for_options(&options, &CountOptions::default());
+            for chunk in s.chunks(size) {
+                counter.update(chunk);
+            }
+            let result = counter.finish();
+            [
+                result.lf_endings,
+                result.crlf_endings,
+                result.cr_endings,
+                result.unicode_endings,
+            ]
+        };
+        for size in [1, 2, 64] {
+            assert_eq!(endings(b"", size), [0; 4]);
+            assert_eq!(endings(b"a\nb\r\nc\rd", size), [1, 1, 1, 0]);
+            assert_eq!(endings(b"\r\r\n\n\r", size), [1, 1, 2, 0]);
+            assert_eq!(
+                endings("\u{85}\u{2028}\u{2029}\n".as_bytes(), size),
+                [1, 0, 0, 3]
+            );
+            // an invalid byte between a CR and a LF
+            assert_eq!(endings(b"\r\xff\n", size), [1, 0, 1, 0]);
+        }
+        // only counted when displayed
+        assert_eq!(counts_in_chunks(b"a\r\n", 1).crlf_endings, 0);
+    }
+
+    #[test]
+    fn test_line_terminator() {
+        let lines = |s: &[u8], line_terminator: LineTerminator, size: usize| {
+            let count_options = CountOptions {
+                line_terminator,
+                ..CountOptions::default()
+            };
+            let options = DisplayOptions::with_lines_only()
+                .join(&DisplayOptions::with_blank_lines_only())
+                .join(&DisplayOptions::with_non_blank_lines_only());
+            let mut counter = Counter::for_options(&options, &count_options);
+            for chunk in s.chunks(size) {
+                counter.update(chunk);
+            }
+            let result = counter.finish();
+            (result.lines, result.blank_lines, result.non_blank_lines)
+        };
+        let s = "a\r\n\r\nb\rc\nd\u{2028}".as_bytes();
+        for size in [1, 2, 64] {
+            assert_eq!(lines(s, LineTerminator::Lf, size), (4, 1, 3));
+            assert_eq!(lines(s, LineTerminator::Crlf, size), (3, 1, 2));
+            assert_eq!(lines(s, LineTerminator::Cr, size), (4, 1, 3));
+            assert_eq!(lines(s, LineTerminator::Any, size), (5, 1, 4));
+            // the last line has no terminator of the mode
+            assert_eq!(lines(b"a\r", LineTerminator::Lf, size), (1, 0, 1));
+            assert_eq!(lines(b"a\n", LineTerminator::Crlf, size), (1, 0, 1));
+            assert_eq!(lines(b"a\r\n", LineTerminator::Any, size), (1, 0, 1));
+            assert_eq!(lines(b"a\r\n\xff", LineTerminator::Any, size), (2, 0, 2));
+        }
+    }
+
+    #[test]
+    fn test_max_line_length() {
+        let counts = |s: &str| counts_in_chunks(s.as_bytes(), 3).max_line_length;
+        assert_eq!(counts(""), 0);
+        assert_eq!(counts("abc\nabcde\nab"), 5);
+        assert_eq!(counts("abc\nabcdefg"), 7);
+        // tabs move to the next multiple of 8
+        assert_eq!(counts("a\tb\n"), 9);
+        assert_eq!(counts("\t\t"), 16);
+        // wide characters take 2 columns and combining marks none
+        assert_eq!(counts("日本語\n"), 6);
+        assert_eq!(counts("e\u{301}e\u{301}\n"), 2);
+        // carriage returns start the line again
+        assert_eq!(counts("abcdef\rab\r\n"), 6);
+        assert_eq!(counts("ab\rabcdef\n"), 6);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::file_result::NUM_COUNTS;
+
 /// Struct to hold which counts will be displayed to the user
 /// based on command line options.
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -6,6 +8,16 @@ pub struct DisplayOptions {
     words: bool,
     chars: bool,
     bytes: bool,
+    max_line_length: bool,
+    blank_lines: bool,
+    non_blank_lines: bool,
+    /// the precision of the sketch estimating the distinct words, if they
+    /// are displayed
+    distinct_words: Option<u8>,
+    graphemes: bool,
+    columns: bool,
+    /// the lines ending in each kind of line terminator
+    line_endings: bool,
 }
 
 impl DisplayOptions {
@@ -15,6 +27,13 @@ impl DisplayOptions {
             words,
             chars,
             bytes,
+            max_line_length: false,
+            blank_lines: false,
+            non_blank_lines: false,
+            distinct_words: None,
+            graphemes: false,
+            columns: false,
+            line_endings: false,
         }
     }
 
@@ -25,6 +44,13 @@ impl DisplayOptions {
         self.words = self.words || other.words;
         self.chars = self.chars || other.chars;
         self.bytes = self.bytes || other.bytes;
+        self.max_line_length = self.max_line_length || other.max_line_length;
+        self.blank_lines = self.blank_lines || other.blank_lines;
+        self.non_blank_lines = self.non_blank_lines || other.non_blank_lines;
+        self.distinct_words = self.distinct_words.max(other.distinct_words);
+        self.graphemes = self.graphemes || other.graphemes;
+        self.columns = self.columns || other.columns;
+        self.line_endings = self.line_endings || other.line_endings;
     }
 
     /// Join `self` with `other` by disjuncting their respective fields,
@@ -42,6 +68,16 @@ impl DisplayOptions {
         self.words = self.words && other.words;
         self.chars = self.chars && other.chars;
         self.bytes = self.bytes && other.bytes;
+        self.max_line_length = self.max_line_length && other.max_line_length;
+        self.blank_lines = self.blank_lines && other.blank_lines;
+        self.non_blank_lines = self.non_blank_lines && other.non_blank_lines;
+        self.distinct_words = self
+            .distinct_words
+            .zip(other.distinct_words)
+            .map(|(a, b)| a.min(b));
+        self.graphemes = self.graphemes && other.graphemes;
+        self.columns = self.columns && other.columns;
+        self.line_endings = self.line_endings && other.line_endings;
     }
 
     /// Meet `self` with `other` by conjuncting their respective fields,
@@ -59,7 +95,56 @@ impl DisplayOptions {
 
     /// Returns `true` if all display options are off.
     pub fn all_off(&self) -> bool {
-        !(self.lines || self.words || self.chars || self.bytes)
+        let options: [bool; NUM_COUNTS] = self.into();
+        !options.contains(&true)
+    }
+
+    /// Returns `true` if any of the counts to display require decoding
+    /// the input into characters, i.e. anything other than lines and bytes.
+    pub fn needs_decoding(&self) -> bool {
+        self.words
+            || self.chars
+            || self.max_line_length
+            || self.blank_lines
+            || self.non_blank_lines
+            || self.distinct_words.is_some()
+            || self.graphemes
+            || self.columns
+            || self.line_endings
+    }
+
+    /// Returns `true` if all counts to display can be computed from byte
+    /// ranges counted separately, see `PartialResult`, which can't compute
+    /// the width of lines, grapheme clusters or the kinds of line endings.
+    pub fn splittable(&self) -> bool {
+        !self.max_line_length && !self.columns && !self.graphemes && !self.line_endings
+    }
+
+    /// Returns `true` if the maximum line length is displayed.
+    pub fn max_line_length(&self) -> bool {
+        self.max_line_length
+    }
+
+    /// The precision of the sketch estimating the distinct words, if they
+    /// are displayed.
+    pub fn distinct_words(&self) -> Option<u8> {
+        self.distinct_words
+    }
+
+    /// Returns `true` if the grapheme clusters are displayed.
+    pub fn graphemes(&self) -> bool {
+        self.graphemes
+    }
+
+    /// Returns `true` if the total display width of lines is displayed.
+    pub fn columns(&self) -> bool {
+        self.columns
+    }
+
+    /// Returns `true` if the lines ending in each kind of line terminator
+    /// are displayed.
+    pub fn line_endings(&self) -> bool {
+        self.line_endings
     }
 
     /// Create a `DisplayOption` with only lines on.
@@ -81,6 +166,63 @@ impl DisplayOptions {
     pub fn with_bytes_only() -> Self {
         Self::new(false, false, false, true)
     }
+
+    /// Create a `DisplayOption` with only max line length on.
+    pub fn with_max_line_length_only() -> Self {
+        Self {
+            max_line_length: true,
+            ..Self::default()
+        }
+    }
+
+    /// Create a `DisplayOption` with only blank lines on.
+    pub fn with_blank_lines_only() -> Self {
+        Self {
+            blank_lines: true,
+            ..Self::default()
+        }
+    }
+
+    /// Create a `DisplayOption` with only non-blank lines on.
+    pub fn with_non_blank_lines_only() -> Self {
+        Self {
+            non_blank_lines: true,
+            ..Self::default()
+        }
+    }
+
+    /// Create a `DisplayOption` with only the distinct words on, estimated
+    /// with a sketch of `precision`.
+    pub fn with_distinct_words_only(precision: u8) -> Self {
+        Self {
+            distinct_words: Some(precision),
+            ..Self::default()
+        }
+    }
+
+    /// Create a `DisplayOption` with only grapheme clusters on.
+    pub fn with_graphemes_only() -> Self {
+        Self {
+            graphemes: true,
+            ..Self::default()
+        }
+    }
+
+    /// Create a `DisplayOption` with only the total display width on.
+    pub fn with_columns_only() -> Self {
+        Self {
+            columns: true,
+            ..Self::default()
+        }
+    }
+
+    /// Create a `DisplayOption` with only the line ending counts on.
+    pub fn with_line_endings_only() -> Self {
+        Self {
+            line_endings: true,
+            ..Self::default()
+        }
+    }
 }
 
 impl Default for DisplayOptions {
@@ -90,16 +232,34 @@ impl Default for DisplayOptions {
     }
 }
 
-/// Convert `options` to a bool array of length 4.
-impl From<&DisplayOptions> for [bool; 4] {
+/// Convert `options` to a bool array of length `NUM_COUNTS`. The distinct
+/// words turn on both the estimate and its error bound, and the line endings
+/// turn on the count of each kind.
+impl From<&DisplayOptions> for [bool; NUM_COUNTS] {
     fn from(options: &DisplayOptions) -> Self {
-        [options.lines, options.words, options.chars, options.bytes]
+        [
+            options.lines,
+            options.words,
+            options.chars,
+            options.bytes,
+            options.max_line_length,
+            options.blank_lines,
+            options.non_blank_lines,
+            options.distinct_words.is_some(),
+            options.distinct_words.is_some(),
+            options.graphemes,
+            options.columns,
+            options.line_endings,
+            options.line_endings,
+            options.line_endings,
+            options.line_endings,
+        ]
     }
 }
 
 /// Calculate how many options are turned on.
 pub fn num_to_display(options: &DisplayOptions) -> u8 {
-    Into::<[bool; 4]>::into(options)
+    Into::<[bool; NUM_COUNTS]>::into(options)
         .into_iter()
         .fold(0u8, |acc, b| if b { acc + 1 } else { acc })
 }
@@ -110,7 +270,35 @@ mod test {
     #[test]
     fn test_all_off() {
         assert!(&DisplayOptions::default().all_off());
-        assert_eq!(false, DisplayOptions::default_options().all_off());
+        assert!(!DisplayOptions::default_options().all_off());
+        assert!(!DisplayOptions::with_max_line_length_only().all_off());
+        assert!(!DisplayOptions::with_non_blank_lines_only().all_off());
+    }
+
+    #[test]
+    fn test_needs_decoding() {
+        assert!(DisplayOptions::default_options().needs_decoding());
+        assert!(DisplayOptions::with_chars_only().needs_decoding());
+        assert!(!DisplayOptions::with_lines_only().needs_decoding());
+        assert!(!DisplayOptions::new(true, false, false, true).needs_decoding());
+        assert!(!DisplayOptions::default().needs_decoding());
+        assert!(DisplayOptions::with_max_line_length_only().needs_decoding());
+        assert!(DisplayOptions::with_blank_lines_only().needs_decoding());
+        assert!(DisplayOptions::with_non_blank_lines_only().needs_decoding());
+        assert!(DisplayOptions::with_distinct_words_only(4).needs_decoding());
+        assert!(DisplayOptions::with_graphemes_only().needs_decoding());
+        assert!(DisplayOptions::with_columns_only().needs_decoding());
+        assert!(DisplayOptions::with_line_endings_only().needs_decoding());
+    }
+
+    #[test]
+    fn test_splittable() {
+        assert!(DisplayOptions::default_options().splittable());
+        assert!(DisplayOptions::with_distinct_words_only(4).splittable());
+        assert!(!DisplayOptions::with_max_line_length_only().splittable());
+        assert!(!DisplayOptions::with_columns_only().splittable());
+        assert!(!DisplayOptions::with_graphemes_only().splittable());
+        assert!(!DisplayOptions::with_line_endings_only().splittable());
     }
 
     #[test]
@@ -130,4 +318,19 @@ mod test {
         assert_eq!(def_ops, off_ops.join(&def_ops));
         assert_eq!(on_ops, on_ops.join(&def_ops));
     }
+
+    #[test]
+    fn test_num_to_display() {
+        assert_eq!(num_to_display(&DisplayOptions::default()), 0);
+        assert_eq!(num_to_display(&DisplayOptions::default_options()), 3);
+        let options =
+            DisplayOptions::with_lines_only().join(&DisplayOptions::with_max_line_length_only());
+        assert_eq!(num_to_display(&options), 2);
+        // the estimate of the distinct words and its error bound
+        assert_eq!(
+            num_to_display(&DisplayOptions::with_distinct_words_only(4)),
+            2
+        );
+        assert_eq!(num_to_display(&DisplayOptions::with_line_endings_only()), 4);
+    }
 }
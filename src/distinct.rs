@@ -0,0 +1,343 @@
+/// The default number of bits of a word hash used to pick a register of a
+/// `Sketch`, giving 2^12 registers and a standard error of about 1.6%.
+pub const DEFAULT_PRECISION: u8 = 12;
+
+/// The smallest precision a `Sketch` can have.
+pub const MIN_PRECISION: u8 = 4;
+
+/// The largest precision a `Sketch` can have.
+pub const MAX_PRECISION: u8 = 18;
+
+/// The Mersenne prime 2^61 - 1 that `WordHash` computes hashes modulo.
+const MODULUS: u64 = (1 << 61) - 1;
+
+/// The base of the polynomial computed by `WordHash`.
+const BASE: u64 = 0x0a3b_1957_c3f2_8d65;
+
+/// Multiply `a` and `b` modulo `MODULUS`, both must be less than it.
+fn mul_mod(a: u64, b: u64) -> u64 {
+    let product = a as u128 * b as u128;
+    let sum = (product as u64 & MODULUS) + (product >> 61) as u64;
+    let sum = (sum & MODULUS) + (sum >> 61);
+    if sum >= MODULUS {
+        sum - MODULUS
+    } else {
+        sum
+    }
+}
+
+/// Add `a` and `b` modulo `MODULUS`, both must be less than it.
+fn add_mod(a: u64, b: u64) -> u64 {
+    let sum = a + b;
+    if sum >= MODULUS {
+        sum - MODULUS
+    } else {
+        sum
+    }
+}
+
+/// A polynomial hash of the bytes of a word, which can be extended a few
+/// bytes at a time or joined with the hash of the bytes that follow it, so
+/// a word split across chunks or byte ranges hashes the same as it does
+/// when it is seen at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WordHash {
+    hash: u64,
+    /// `BASE` to the power of `len`
+    power: u64,
+    len: usize,
+}
+
+impl Default for WordHash {
+    fn default() -> Self {
+        Self {
+            hash: 0,
+            power: 1,
+            len: 0,
+        }
+    }
+}
+
+impl WordHash {
+    /// Compute the hash of `bytes`.
+    pub fn of(bytes: &[u8]) -> Self {
+        let mut hash = Self::default();
+        hash.extend(bytes);
+        hash
+    }
+
+    /// Returns `true` if no bytes have been hashed.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Extend the hash with `bytes`, which follow the bytes hashed so far.
+    pub fn extend(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            // offset by one so leading zero bytes change the hash
+            self.hash = add_mod(mul_mod(self.hash, BASE), b as u64 + 1);
+            self.power = mul_mod(self.power, BASE);
+        }
+        self.len += bytes.len();
+    }
+
+    /// The hash of the bytes of `self` followed by the bytes of `other`.
+    pub fn join(&self, other: &Self) -> Self {
+        Self {
+            hash: add_mod(mul_mod(self.hash, other.power), other.hash),
+            power: mul_mod(self.power, other.power),
+            len: self.len + other.len,
+        }
+    }
+
+    /// Spread the hash over all 64 bits, using the finalizer of SplitMix64.
+    fn mix(&self) -> u64 {
+        let mut x = self.hash;
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        x ^ (x >> 31)
+    }
+}
+
+/// A HyperLogLog sketch estimating the number of distinct words inserted
+/// into it using a fixed amount of memory, 2^precision bytes.
+///
+/// Sketches with the same precision are merged by taking the maximum of
+/// each register, which gives the same sketch as inserting the words of
+/// both into one, so sketches of files or byte ranges counted separately
+/// can be combined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sketch {
+    /// the largest position of the first set bit seen for the hashes
+    /// mapped to each register
+    registers: Vec<u8>,
+}
+
+impl Sketch {
+    /// Create an empty sketch with 2^`precision` registers. `precision`
+    /// must be between `MIN_PRECISION` and `MAX_PRECISION`.
+    pub fn new(precision: u8) -> Self {
+        assert!((MIN_PRECISION..=MAX_PRECISION).contains(&precision));
+        Self {
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    /// The number of bits of a hash used to pick a register.
+    pub fn precision(&self) -> u8 {
+        self.registers.len().trailing_zeros() as u8
+    }
+
+    /// Insert the word with hash `word`.
+    pub fn insert(&mut self, word: &WordHash) {
+        let precision = self.precision();
+        let hash = word.mix();
+        let register = (hash >> (64 - precision)) as usize;
+        // the marker bit bounds the rank when the remaining bits are all 0
+        let rest = (hash << precision) | (1 << (precision - 1));
+        let rank = rest.leading_zeros() as u8 + 1;
+        self.registers[register] = self.registers[register].max(rank);
+    }
+
+    /// Merge the words inserted into `other` into `self`. Both must have the
+    /// same precision.
+    pub fn merge_mut(&mut self, other: &Self) {
+        assert_eq!(self.registers.len(), other.registers.len());
+        for (register, &other) in self.registers.iter_mut().zip(&other.registers) {
+            *register = (*register).max(other);
+        }
+    }
+
+    /// Estimate the number of distinct words inserted.
+    pub fn estimate(&self) -> usize {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum: f64 = self.registers.iter().map(|&r| 2f64.powi(-(r as i32))).sum();
+        let raw = alpha * m * m / sum;
+        let zeros = self.registers.iter().filter(|&&r| r == 0).count();
+        // linear counting is more accurate while many registers are empty
+        let estimate = if raw <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            raw
+        };
+        estimate.round() as usize
+    }
+
+    /// The relative standard error of the estimate.
+    pub fn standard_error(&self) -> f64 {
+        1.04 / (self.registers.len() as f64).sqrt()
+    }
+
+    /// How far the estimate may be from the true number of distinct words,
+    /// two standard errors, which the estimate is within about 95% of the
+    /// time.
+    pub fn error_bound(&self) -> usize {
+        (2.0 * self.standard_error() * self.estimate() as f64).ceil() as usize
+    }
+}
+
+/// The distinct words of a run of bytes that may start or end in the middle
+/// of a word, along with what is needed to join it to the runs next to it.
+///
+/// The run must decode to the same characters as it does as part of the
+/// whole input, i.e. it must not split a multi-byte character.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialSketch {
+    /// the words that are complete within the run
+    sketch: Sketch,
+    /// the bytes before the first whitespace, or the whole run if there is
+    /// no whitespace
+    first: WordHash,
+    /// the bytes after the last whitespace
+    last: WordHash,
+    /// whether there is whitespace anywhere
+    has_whitespace: bool,
+}
+
+impl PartialSketch {
+    /// Compute the partial sketch of `bytes` with the given `precision`.
+    /// Bytes that are not valid UTF-8 are part of words.
+    pub fn of(bytes: &[u8], precision: u8) -> Self {
+        let mut partial = Self {
+            sketch: Sketch::new(precision),
+            first: WordHash::default(),
+            last: WordHash::default(),
+            has_whitespace: false,
+        };
+        let mut word = WordHash::default();
+        for chunk in bytes.utf8_chunks() {
+            for c in chunk.valid().chars() {
+                if !c.is_whitespace() {
+                    let mut buf = [0; 4];
+                    word.extend(c.encode_utf8(&mut buf).as_bytes());
+                    continue;
+                }
+                let word = std::mem::take(&mut word);
+                if !partial.has_whitespace {
+                    partial.first = word;
+                    partial.has_whitespace = true;
+                } else if !word.is_empty() {
+                    partial.sketch.insert(&word);
+                }
+            }
+            word.extend(chunk.invalid());
+        }
+        if partial.has_whitespace {
+            partial.last = word;
+        } else {
+            partial.first = word;
+        }
+        partial
+    }
+
+    /// Join `self` with the partial sketch of the run that directly follows
+    /// it.
+    pub fn join(&self, other: &Self) -> Self {
+        let mut sketch = self.sketch.clone();
+        sketch.merge_mut(&other.sketch);
+        let (first, last) = match (self.has_whitespace, other.has_whitespace) {
+            (false, false) => (self.first.join(&other.first), WordHash::default()),
+            (false, true) => (self.first.join(&other.first), other.last),
+            (true, false) => (self.first, self.last.join(&other.first)),
+            (true, true) => {
+                // the word between the last whitespace of `self` and the
+                // first whitespace of `other` is complete
+                let middle = self.last.join(&other.first);
+                if !middle.is_empty() {
+                    sketch.insert(&middle);
+                }
+                (self.first, other.last)
+            }
+        };
+        Self {
+            sketch,
+            first,
+            last,
+            has_whitespace: self.has_whitespace || other.has_whitespace,
+        }
+    }
+
+    /// Treat `self` as the partial sketch of a whole input and return its
+    /// sketch, including the words at its start and end.
+    pub fn finish(&self) -> Sketch {
+        let mut sketch = self.sketch.clone();
+        for word in [&self.first, &self.last] {
+            if !word.is_empty() {
+                sketch.insert(word);
+            }
+        }
+        sketch
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// The sketch of the words of `s` split at whitespace.
+    fn sketch_of(s: &str, precision: u8) -> Sketch {
+        let mut sketch = Sketch::new(precision);
+        for word in s.split_whitespace() {
+            sketch.insert(&WordHash::of(word.as_bytes()));
+        }
+        sketch
+    }
+
+    #[test]
+    fn test_word_hash_join() {
+        let s = b"\x00hello\xffworld";
+        for i in 0..=s.len() {
+            let (a, b) = s.split_at(i);
+            assert_eq!(WordHash::of(a).join(&WordHash::of(b)), WordHash::of(s));
+        }
+        assert_ne!(WordHash::of(b"\x00a"), WordHash::of(b"a"));
+        assert!(WordHash::of(b"").is_empty());
+    }
+
+    #[test]
+    fn test_estimate() {
+        assert_eq!(Sketch::new(DEFAULT_PRECISION).estimate(), 0);
+        assert_eq!(sketch_of("a b a c b", DEFAULT_PRECISION).estimate(), 3);
+        for precision in [MIN_PRECISION, DEFAULT_PRECISION, MAX_PRECISION] {
+            let words: Vec<String> = (0..50_000).map(|i| format!("word{i}")).collect();
+            let sketch = sketch_of(&words.join(" "), precision);
+            let error = sketch.estimate().abs_diff(words.len());
+            // allow for an unlucky hash with three standard errors
+            assert!(
+                error as f64 <= 1.5 * sketch.error_bound() as f64,
+                "precision {precision}: estimated {}",
+                sketch.estimate()
+            );
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut sketch = sketch_of("a b c", 8);
+        sketch.merge_mut(&sketch_of("c d", 8));
+        assert_eq!(sketch, sketch_of("a b c d", 8));
+        assert_eq!(sketch.precision(), 8);
+    }
+
+    #[test]
+    fn test_partial_sketch() {
+        let s = "héllo wörld\u{3000}wörld  \n\u{a0}\u{ff}x héllo\t";
+        let bytes = s.as_bytes();
+        let expected = sketch_of(s, 6);
+        assert_eq!(PartialSketch::of(bytes, 6).finish(), expected);
+        for i in (0..=bytes.len()).filter(|&i| s.is_char_boundary(i)) {
+            for j in (i..=bytes.len()).filter(|&j| s.is_char_boundary(j)) {
+                let joined = PartialSketch::of(&bytes[..i], 6)
+                    .join(&PartialSketch::of(&bytes[i..j], 6))
+                    .join(&PartialSketch::of(&bytes[j..], 6));
+                assert_eq!(joined.finish(), expected, "split at {i}, {j}");
+            }
+        }
+    }
+}
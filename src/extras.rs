@@ -0,0 +1,177 @@
+use std::fs::File;
+use std::io::{self, Read};
+
+use crate::code_stats::{CodeCounter, CodeStats, Language};
+use crate::count_options::CountOptions;
+use crate::counter::{for_each_chunk, Counter};
+use crate::display_options::DisplayOptions;
+use crate::file_result::FileResult;
+use crate::input::map_file;
+use crate::word_freq::{WordCounter, WordCounts, WordFreqOptions};
+
+/// What is counted for an input besides its `FileResult`, see
+/// `counts_and_extras_for_reader`.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Extras {
+    /// the language of the input and its code statistics, if it has a
+    /// known language
+    pub code_stats: Option<(&'static Language, CodeStats)>,
+    /// the words counted with `--word-freq`
+    pub word_counts: Option<WordCounts>,
+}
+
+/// Computes the counts of a `Counter` along with the `Extras` of an input
+/// incrementally from a stream of byte chunks, so each chunk is only read
+/// once.
+#[derive(Debug, Clone)]
+pub struct ExtrasCounter {
+    counter: Counter,
+    language: Option<&'static Language>,
+    code_counter: Option<CodeCounter>,
+    word_counter: Option<WordCounter>,
+}
+
+impl ExtrasCounter {
+    /// Create an `ExtrasCounter` computing the counts needed to display
+    /// `options`, the code statistics in `language` if it is known, and the
+    /// word frequencies if `word_freq` is set.
+    pub fn new(
+        options: &DisplayOptions,
+        count_options: &CountOptions,
+        language: Option<&'static Language>,
+        word_freq: Option<&WordFreqOptions>,
+    ) -> Self {
+        Self {
+            counter: Counter::for_options(options, count_options),
+            language,
+            code_counter: language.map(CodeCounter::new),
+            word_counter: word_freq.map(|options| WordCounter::new(options, count_options)),
+        }
+    }
+
+    /// Count the next chunk of the input.
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.counter.update(bytes);
+        if let Some(code_counter) = &mut self.code_counter {
+            code_counter.update(bytes);
+        }
+        if let Some(word_counter) = &mut self.word_counter {
+            word_counter.update(bytes);
+        }
+    }
+
+    /// Finish counting and return the counts and extras for all chunks seen.
+    pub fn finish(self) -> (FileResult, Extras) {
+        let extras = Extras {
+            code_stats: self
+                .language
+                .zip(self.code_counter.map(CodeCounter::finish)),
+            word_counts: self.word_counter.map(WordCounter::finish),
+        };
+        (self.counter.finish(), extras)
+    }
+}
+
+/// Compute the counts needed to display `options`, the code statistics in
+/// `language` if it is known, and the word frequencies if `word_freq` is
+/// set, in a single pass over everything read from `reader`.
+pub fn counts_and_extras_for_reader<R: Read>(
+    reader: &mut R,
+    options: &DisplayOptions,
+    count_options: &CountOptions,
+    language: Option<&'static Language>,
+    word_freq: Option<&WordFreqOptions>,
+) -> io::Result<(FileResult, Extras)> {
+    let mut counter = ExtrasCounter::new(options, count_options, language, word_freq);
+    for_each_chunk(reader, |chunk| counter.update(chunk))?;
+    Ok(counter.finish())
+}
+
+/// Compute the counts and extras like `counts_and_extras_for_reader` for
+/// the rest of `file` by memory mapping it, see `counts_for_mapped_file`.
+pub fn counts_and_extras_for_mapped_file(
+    file: &mut File,
+    options: &DisplayOptions,
+    count_options: &CountOptions,
+    language: Option<&'static Language>,
+    word_freq: Option<&WordFreqOptions>,
+) -> io::Result<(FileResult, Extras)> {
+    let mut counter = ExtrasCounter::new(options, count_options, language, word_freq);
+    match map_file(file) {
+        Some(map) => counter.update(&map),
+        None => for_each_chunk(file, |chunk| counter.update(chunk))?,
+    }
+    Ok(counter.finish())
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::Path;
+
+    use super::*;
+    use crate::code_stats::language_for_path;
+
+    #[test]
+    fn test_counts_and_extras() {
+        let s = "// one\nfn two() {}\n";
+        let count = |language, word_freq| {
+            counts_and_extras_for_reader(
+                &mut s.as_bytes(),
+                &DisplayOptions::default_options(),
+                &CountOptions::default(),
+                language,
+                word_freq,
+            )
+            .unwrap()
+        };
+        let (result, extras) = count(language_for_path(Path::new("main.rs")), None);
+        assert_eq!(result.lines, 2);
+        assert_eq!(result.words, 5);
+        let (rust, stats) = extras.code_stats.unwrap();
+        assert_eq!(rust.name, "Rust");
+        assert_eq!(stats, CodeStats::new(1, 1, 0));
+        assert_eq!(extras.word_counts, None);
+
+        let (result, extras) = count(None, Some(&WordFreqOptions::default()));
+        assert_eq!(result.words, 5);
+        assert_eq!(extras.code_stats, None);
+        let word_counts = extras.word_counts.unwrap();
+        assert_eq!(word_counts.total(), 5);
+        assert_eq!(word_counts.top(1), [(String::from("//"), 1)]);
+    }
+
+    #[test]
+    fn test_counts_and_extras_for_mapped_file() {
+        let path = std::env::temp_dir().join(format!("wcrs-extras-{}.rs", std::process::id()));
+        let s = "// one\nfn two() {}\n".repeat(1000);
+        fs::write(&path, &s).unwrap();
+        let options = DisplayOptions::default_options();
+        let count_options = CountOptions::default();
+        let language = language_for_path(&path);
+        let word_freq = WordFreqOptions::default();
+        let mapped = counts_and_extras_for_mapped_file(
+            &mut File::open(&path).unwrap(),
+            &options,
+            &count_options,
+            language,
+            Some(&word_freq),
+        )
+        .unwrap();
+        let read = counts_and_extras_for_reader(
+            &mut s.as_bytes(),
+            &options,
+            &count_options,
+            language,
+            Some(&word_freq),
+        )
+        .unwrap();
+        assert_eq!(mapped, read);
+        let (result, extras) = mapped;
+        assert_eq!(result.lines, 2000);
+        assert_eq!(extras.code_stats.unwrap().1, CodeStats::new(1000, 1000, 0));
+        assert_eq!(extras.word_counts.unwrap().total(), 5000);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,12 +1,39 @@
+use std::fs::File;
+use std::io;
+
+use crate::count_options::CountOptions;
+use crate::counter::Counter;
 use crate::display_options::DisplayOptions;
+use crate::distinct::Sketch;
+use crate::input::map_file;
+use crate::width::ColumnCounter;
+
+/// The number of counts in a `FileResult`, see `COUNT_NAMES`.
+pub const NUM_COUNTS: usize = 15;
 
-/// Stores line, word, character, and byte counts for a file
+/// Stores line, word, character, and byte counts for a file, along with
+/// the display width of its longest line, how many lines are blank,
+/// i.e. only contain whitespace, or not, a sketch of its distinct words
+/// if they are estimated, its extended grapheme clusters, i.e. user
+/// perceived characters, the total display width of its lines, and how
+/// many of its lines end in a lone line feed, a CRLF, a lone carriage return
+/// or a Unicode next line, line separator or paragraph separator
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct FileResult {
     pub lines: usize,
     pub words: usize,
     pub chars: usize,
     pub bytes: usize,
+    pub max_line_length: usize,
+    pub blank_lines: usize,
+    pub non_blank_lines: usize,
+    pub distinct_words: Option<Sketch>,
+    pub graphemes: usize,
+    pub columns: usize,
+    pub lf_endings: usize,
+    pub crlf_endings: usize,
+    pub cr_endings: usize,
+    pub unicode_endings: usize,
 }
 
 impl FileResult {
@@ -16,16 +43,43 @@ impl FileResult {
             words,
             chars,
             bytes,
+            max_line_length: 0,
+            blank_lines: 0,
+            non_blank_lines: 0,
+            distinct_words: None,
+            graphemes: 0,
+            columns: 0,
+            lf_endings: 0,
+            crlf_endings: 0,
+            cr_endings: 0,
+            unicode_endings: 0,
         }
     }
 
     /// Add the counts in `self` and `other`, storing
-    /// the results in `self`
+    /// the results in `self`. The longest line of both is the
+    /// longer of the two, so `max_line_length` is the maximum
+    /// rather than the sum, and the distinct words sketches are
+    /// merged.
     pub fn add_mut(&mut self, other: &Self) {
         self.lines += other.lines;
         self.words += other.words;
         self.chars += other.chars;
         self.bytes += other.bytes;
+        self.max_line_length = self.max_line_length.max(other.max_line_length);
+        self.blank_lines += other.blank_lines;
+        self.non_blank_lines += other.non_blank_lines;
+        self.graphemes += other.graphemes;
+        self.columns += other.columns;
+        self.lf_endings += other.lf_endings;
+        self.crlf_endings += other.crlf_endings;
+        self.cr_endings += other.cr_endings;
+        self.unicode_endings += other.unicode_endings;
+        match (&mut self.distinct_words, &other.distinct_words) {
+            (Some(sketch), Some(other)) => sketch.merge_mut(other),
+            (None, Some(other)) => self.distinct_words = Some(other.clone()),
+            _ => {}
+        }
     }
 
     /// Add the counts in `self` and `other` returning the result
@@ -42,53 +96,143 @@ impl Default for FileResult {
     }
 }
 
-/// Convert a FileResult to usize array of length 4
-impl From<&FileResult> for [usize; 4] {
+/// The names of the counts in the order of the array conversions of
+/// `FileResult` and `DisplayOptions`.
+pub const COUNT_NAMES: [&str; NUM_COUNTS] = [
+    "lines",
+    "words",
+    "chars",
+    "bytes",
+    "max_line_length",
+    "blank_lines",
+    "non_blank_lines",
+    "distinct_words",
+    "distinct_words_error",
+    "graphemes",
+    "columns",
+    "lf_endings",
+    "crlf_endings",
+    "cr_endings",
+    "unicode_endings",
+];
+
+/// Convert a FileResult to usize array of length `NUM_COUNTS`, with the
+/// estimate of the distinct words and its error bound
+impl From<&FileResult> for [usize; NUM_COUNTS] {
     fn from(value: &FileResult) -> Self {
-        [value.lines, value.words, value.chars, value.bytes]
+        let distinct = value.distinct_words.as_ref();
+        [
+            value.lines,
+            value.words,
+            value.chars,
+            value.bytes,
+            value.max_line_length,
+            value.blank_lines,
+            value.non_blank_lines,
+            distinct.map_or(0, Sketch::estimate),
+            distinct.map_or(0, Sketch::error_bound),
+            value.graphemes,
+            value.columns,
+            value.lf_endings,
+            value.crlf_endings,
+            value.cr_endings,
+            value.unicode_endings,
+        ]
     }
 }
 
+/// The names of the counts turned on in `options`, in `COUNT_NAMES` order.
+pub fn displayed_names(options: &DisplayOptions) -> Vec<&'static str> {
+    let options_arr: [bool; NUM_COUNTS] = options.into();
+
+    options_arr
+        .into_iter()
+        .zip(COUNT_NAMES)
+        .filter(|(v, _)| *v)
+        .map(|(_, name)| name)
+        .collect()
+}
+
+/// The counts in `result` for fields turned on in `options`, in
+/// `COUNT_NAMES` order.
+pub fn displayed_counts(result: &FileResult, options: &DisplayOptions) -> Vec<usize> {
+    let options_arr: [bool; NUM_COUNTS] = options.into();
+    let result_arr: [usize; NUM_COUNTS] = result.into();
+
+    options_arr
+        .into_iter()
+        .zip(result_arr)
+        .filter(|(v, _)| *v)
+        .map(|(_, count)| count)
+        .collect()
+}
+
 /// Produce a string representation of `result` only displaying the
-/// counts for fields turned on in `options`
-pub fn file_result_string(result: &FileResult, options: &DisplayOptions) -> String {
-    let options_arr: [bool; 4] = options.into();
-    let result_arr: [usize; 4] = result.into();
-
-    // TODO: I don't like this
-    let mut s = String::new();
-    options_arr.into_iter().enumerate().for_each(|(i, v)| {
-        if v {
-            s.push_str(&format!("{:<10}", &result_arr[i]));
-        }
-    });
-    s
+/// counts for fields turned on in `options`. Each count is right aligned
+/// in a column `width` characters wide and columns are separated by a
+/// single space, as in GNU wc.
+pub fn file_result_string(result: &FileResult, options: &DisplayOptions, width: usize) -> String {
+    displayed_counts(result, options)
+        .into_iter()
+        .map(|count| format!("{:>width$}", count))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Compute the width of the count columns the way GNU wc does, so that
+/// the counts line up without reading the inputs first. `sizes` has the
+/// size of each input that is a regular file and `None` for any other
+/// kind of input, such as a pipe.
+///
+/// The width is the number of digits in the total size of the regular
+/// files, since no count can be larger than that, but at least 7 if there
+/// are other inputs whose size is unknown.
+pub fn column_width(sizes: &[Option<u64>]) -> usize {
+    let minimum_width = if sizes.iter().any(Option::is_none) {
+        7
+    } else {
+        1
+    };
+    let total: u64 = sizes.iter().flatten().sum();
+    let digits = total.checked_ilog10().unwrap_or(0) as usize + 1;
+    digits.max(minimum_width)
 }
 
-/// Compute line, word, character, and byte counts for `s`.
+/// Compute line, word, character, and byte counts for `s`, whether it
+/// is a blank or non-blank line, and its display width, with the whitespace
+/// and tab width of `count_options`. Words are always split at whitespace
+/// as with `WordMode::Whitespace`.
 /// Assumes that `s` does not have any newline characters.
-pub fn counts_for_line(s: &str) -> FileResult {
+pub fn counts_for_line(s: &str, count_options: &CountOptions) -> FileResult {
+    let is_whitespace = |c: char| count_options.whitespace.is_whitespace(c);
+    let mut columns = ColumnCounter::new(count_options.tab_width);
     // skip the first set of whitespace characters
-    // TODO: use is_ascii_whitespace() instead?
-    let start_of_word = match s.find(|c: char| !c.is_whitespace()) {
+    let start_of_word = match s.find(|c: char| !is_whitespace(c)) {
         Some(i) => i,
         None => {
-            let chars = s.chars().count();
+            let chars = s.chars().inspect(|&c| columns.push(c)).count();
             let bytes = s.len();
-            return FileResult::new(0, 0, chars, bytes);
+            columns.end_line();
+            return FileResult {
+                blank_lines: 1,
+                max_line_length: columns.max(),
+                columns: columns.total(),
+                ..FileResult::new(0, 0, chars, bytes)
+            };
         }
     };
     let (whitespace_prefix, rest) = s.split_at(start_of_word);
-    let (wchars, wbytes) = whitespace_prefix
-        .chars()
-        .fold((0, 0), |acc, c: char| (acc.0 + 1, acc.1 + c.len_utf8()));
+    let (wchars, wbytes) = whitespace_prefix.chars().fold((0, 0), |acc, c: char| {
+        columns.push(c);
+        (acc.0 + 1, acc.1 + c.len_utf8())
+    });
 
     let (chars, bytes, words, _) = rest.chars().fold((0, 0, 0, true), |acc, c| {
+        columns.push(c);
         let chars = acc.0 + 1;
         let bytes = acc.1 + c.len_utf8();
         let prev_whitespace = acc.3;
-        // TODO: use is_ascii_whitespace() instead?
-        let curr_whitespace = c.is_whitespace();
+        let curr_whitespace = is_whitespace(c);
         // if the prev character was a whitespace and the current character is not
         // increment the word count
         let incr_words = !curr_whitespace && prev_whitespace;
@@ -96,23 +240,42 @@ pub fn counts_for_line(s: &str) -> FileResult {
 
         (chars, bytes, words, curr_whitespace)
     });
+    columns.end_line();
 
-    FileResult::new(0, words, wchars + chars, wbytes + bytes)
+    FileResult {
+        non_blank_lines: 1,
+        max_line_length: columns.max(),
+        columns: columns.total(),
+        ..FileResult::new(0, words, wchars + chars, wbytes + bytes)
+    }
 }
 
 /// Compute line, word, character, and byte counts for `file`.
-pub fn counts_for_file(file: &str) -> FileResult {
-    // TODO: Should we handle \r\n newlines as well?
-    file.split_inclusive('\n')
-        .fold(FileResult::default(), |acc, l| {
-            let line_result = counts_for_line(l);
-            FileResult::new(
-                acc.lines + 1,
-                acc.words + line_result.words,
-                acc.chars + line_result.chars,
-                acc.bytes + line_result.bytes,
-            )
-        })
+/// `file` does not need to be valid UTF-8, see `Counter` for how invalid
+/// bytes are counted. Lines end at every `\n` like in GNU wc, so a CRLF ends
+/// one line and a lone `\r` none, see `LineTerminator` for counting the
+/// other line endings.
+pub fn counts_for_file(file: &[u8]) -> FileResult {
+    let mut counter = Counter::new();
+    counter.update(file);
+    counter.finish()
+}
+
+/// Compute the counts needed to display `options` for the rest of `file`
+/// by memory mapping it, so its contents are counted without being copied.
+/// Falls back to reading `file` in chunks if it is not a regular file or
+/// can not be mapped.
+pub fn counts_for_mapped_file(
+    file: &mut File,
+    options: &DisplayOptions,
+    count_options: &CountOptions,
+) -> io::Result<FileResult> {
+    let mut counter = Counter::for_options(options, count_options);
+    match map_file(file) {
+        Some(map) => counter.update(&map),
+        None => counter.read_from(file)?,
+    }
+    Ok(counter.finish())
 }
 
 #[cfg(test)]
@@ -123,10 +286,107 @@ mod test {
         let result = FileResult::new(1, 2, 3, 4);
         let options = DisplayOptions::new(true, false, true, false);
 
+        assert_eq!(&file_result_string(&result, &options, 1), "1 3");
+        assert_eq!(&file_result_string(&result, &options, 3), "  1   3");
+
+        let result = FileResult::new(12345, 2, 3, 4);
+        let options = DisplayOptions::default_options();
+        assert_eq!(&file_result_string(&result, &options, 4), "12345    2    4");
+        let options = DisplayOptions::with_words_only();
+        assert_eq!(&file_result_string(&result, &options, 1), "2");
+    }
+
+    #[test]
+    fn test_displayed_counts() {
+        let result = FileResult {
+            max_line_length: 5,
+            ..FileResult::new(1, 2, 3, 4)
+        };
+        let options = DisplayOptions::default_options();
+        assert_eq!(displayed_names(&options), ["lines", "words", "bytes"]);
+        assert_eq!(displayed_counts(&result, &options), [1, 2, 4]);
+        let options = DisplayOptions::with_max_line_length_only();
+        assert_eq!(displayed_names(&options), ["max_line_length"]);
+        assert_eq!(displayed_counts(&result, &options), [5]);
+    }
+
+    #[test]
+    fn test_column_width() {
+        assert_eq!(column_width(&[]), 1);
+        assert_eq!(column_width(&[Some(0)]), 1);
+        assert_eq!(column_width(&[Some(9)]), 1);
+        assert_eq!(column_width(&[Some(10)]), 2);
+        assert_eq!(column_width(&[Some(999), Some(1)]), 4);
+        // inputs of unknown size need at least 7 columns
+        assert_eq!(column_width(&[Some(10), None]), 7);
+        assert_eq!(column_width(&[None, Some(123_456_789)]), 9);
+    }
+
+    #[test]
+    fn test_counts_for_file() {
+        assert_eq!(counts_for_file(b""), FileResult::default());
+        assert_eq!(
+            counts_for_file("one two\nthree\n".as_bytes()),
+            FileResult {
+                max_line_length: 7,
+                non_blank_lines: 2,
+                columns: 12,
+                ..FileResult::new(2, 3, 14, 14)
+            }
+        );
+        // final line without a newline
+        assert_eq!(
+            counts_for_file(b"a\nb"),
+            FileResult {
+                max_line_length: 1,
+                non_blank_lines: 2,
+                columns: 2,
+                ..FileResult::new(2, 2, 3, 3)
+            }
+        );
+        // lines with only whitespace are blank
+        assert_eq!(
+            counts_for_file("a\n \t\n\nb \u{3000}".as_bytes()),
+            FileResult {
+                max_line_length: 8,
+                blank_lines: 2,
+                columns: 13,
+                non_blank_lines: 2,
+                ..FileResult::new(4, 2, 9, 11)
+            }
+        );
+        // binary data
+        assert_eq!(
+            counts_for_file(b"\x00\x9f\x92\n\xff\xfe \x80"),
+            FileResult {
+                max_line_length: 1,
+                non_blank_lines: 2,
+                columns: 1,
+                ..FileResult::new(2, 3, 3, 8)
+            }
+        );
+    }
+
+    #[test]
+    fn test_counts_for_mapped_file() {
+        let contents = "one two\nthree\n";
+        let path = std::env::temp_dir().join(format!("wcrs-mapped-{}", std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        let options = DisplayOptions::new(true, true, true, true)
+            .join(&DisplayOptions::with_max_line_length_only());
+        let mut file = File::open(&path).unwrap();
         assert_eq!(
-            &file_result_string(&result, &options),
-            "1         3         "
+            counts_for_mapped_file(&mut file, &options, &CountOptions::default()).unwrap(),
+            counts_for_file(contents.as_bytes())
         );
+        // empty files can't be mapped
+        std::fs::write(&path, "").unwrap();
+        let mut file = File::open(&path).unwrap();
+        assert_eq!(
+            counts_for_mapped_file(&mut file, &options, &CountOptions::default()).unwrap(),
+            FileResult::default()
+        );
+        std::fs::remove_file(&path).unwrap();
     }
 
     #[test]
@@ -134,5 +394,16 @@ mod test {
         let fr1 = FileResult::new(1, 2, 3, 4);
         let fr2 = FileResult::new(3, 2, 5, 9);
         assert_eq!(fr1.add(&fr2), FileResult::new(4, 4, 8, 13));
+
+        let fr1 = FileResult {
+            max_line_length: 7,
+            ..FileResult::new(1, 2, 3, 4)
+        };
+        let fr2 = FileResult {
+            max_line_length: 5,
+            ..FileResult::new(3, 2, 5, 9)
+        };
+        assert_eq!(fr1.add(&fr2).max_line_length, 7);
+        assert_eq!(fr2.add(&fr1).max_line_length, 7);
     }
 }
@@ -0,0 +1,362 @@
+use std::path::{Component, Path};
+
+/// A part of a `Glob` pattern.
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Token {
+    /// a character that has to match exactly
+    Literal(char),
+    /// `?`, any single character except `/`
+    AnyChar,
+    /// `*`, any sequence of characters except `/`
+    Star,
+    /// `**/`, zero or more whole directories
+    AnyDirs,
+    /// `**` anywhere else, any sequence of characters including `/`
+    AnyPath,
+    /// `[...]`, any character in one of the inclusive `ranges`, or any
+    /// character not in them if `negated`
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Token {
+    /// Returns `true` if the single character token `self` matches `c`.
+    fn matches_char(&self, c: char) -> bool {
+        match self {
+            Token::Literal(literal) => *literal == c,
+            Token::AnyChar => c != '/',
+            Token::Class { negated, ranges } => {
+                c != '/' && ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != *negated
+            }
+            Token::Star | Token::AnyDirs | Token::AnyPath => false,
+        }
+    }
+}
+
+/// A shell style wildcard pattern matched against relative paths, with `/`
+/// separating directories.
+///
+/// `*` and `?` match within a single path component, `**` matches across
+/// them, `[abc]`, `[a-z]` and `[!abc]` match a character class, and `\`
+/// escapes the next character. A pattern without a `/` is matched against
+/// the file name only, so `*.rs` matches Rust files in any directory.
+///
+/// Paths are matched without their `.` components, so `./src/a.rs` matches
+/// like `src/a.rs`. An absolute path keeps its leading `/`, so it is only
+/// matched by patterns starting with `/` or `**/`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Glob {
+    tokens: Vec<Token>,
+    /// whether the pattern is matched against the file name only
+    name_only: bool,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            tokens: tokenize(pattern),
+            name_only: !pattern.contains('/'),
+        }
+    }
+
+    /// Create a `Glob` that is matched against the whole relative path even
+    /// if `pattern` has no `/`.
+    pub fn anchored(pattern: &str) -> Self {
+        Self {
+            tokens: tokenize(pattern),
+            name_only: false,
+        }
+    }
+
+    /// Returns `true` if the relative `path` matches the pattern.
+    pub fn matches(&self, path: &Path) -> bool {
+        self.matches_str(&self.subject(path))
+    }
+
+    /// Returns `true` if the pattern matches the relative `path` of a
+    /// directory, either as it is or as a prefix of the paths in it, like
+    /// `target/**` matches `target`.
+    pub fn matches_dir(&self, path: &Path) -> bool {
+        let subject = self.subject(path);
+        self.matches_str(&subject) || (!self.name_only && self.matches_str(&(subject + "/")))
+    }
+
+    /// The part of `path` the pattern is matched against.
+    fn subject(&self, path: &Path) -> String {
+        if self.name_only {
+            return path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+        }
+        let mut subject = String::new();
+        for component in path.components() {
+            match component {
+                Component::CurDir => continue,
+                Component::RootDir => subject.push('/'),
+                _ => {
+                    if !subject.is_empty() && !subject.ends_with('/') {
+                        subject.push('/');
+                    }
+                    subject.push_str(&component.as_os_str().to_string_lossy());
+                }
+            }
+        }
+        subject
+    }
+
+    fn matches_str(&self, s: &str) -> bool {
+        let chars: Vec<char> = s.chars().collect();
+        matches_tokens(&self.tokens, &chars)
+    }
+}
+
+/// Split `pattern` into tokens. A `[` without a closing `]` is a literal.
+fn tokenize(pattern: &str) -> Vec<Token> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' => {
+                let start = i;
+                while chars.get(i) == Some(&'*') {
+                    i += 1;
+                }
+                if i - start == 1 {
+                    tokens.push(Token::Star);
+                    continue;
+                }
+                // `**` only crosses directories as a whole path component
+                let starts_component = start == 0 || chars[start - 1] == '/';
+                if starts_component && chars.get(i) == Some(&'/') {
+                    tokens.push(Token::AnyDirs);
+                    i += 1;
+                } else {
+                    tokens.push(Token::AnyPath);
+                }
+            }
+            '?' => {
+                tokens.push(Token::AnyChar);
+                i += 1;
+            }
+            '[' => match parse_class(&chars[i + 1..]) {
+                Some((token, len)) => {
+                    tokens.push(token);
+                    i += len + 1;
+                }
+                None => {
+                    tokens.push(Token::Literal('['));
+                    i += 1;
+                }
+            },
+            '\\' if i + 1 < chars.len() => {
+                tokens.push(Token::Literal(chars[i + 1]));
+                i += 2;
+            }
+            c => {
+                tokens.push(Token::Literal(c));
+                i += 1;
+            }
+        }
+    }
+    tokens
+}
+
+/// Parse the character class following a `[`, returning it along with the
+/// number of characters it used including the closing `]`, or `None` if it
+/// is never closed.
+fn parse_class(chars: &[char]) -> Option<(Token, usize)> {
+    let mut i = 0;
+    let negated = matches!(chars.first(), Some('!' | '^'));
+    if negated {
+        i += 1;
+    }
+    let mut ranges = Vec::new();
+    // a `]` straight after the `[` is part of the class
+    let mut first = true;
+    loop {
+        let c = *chars.get(i)?;
+        if c == ']' && !first {
+            return Some((Token::Class { negated, ranges }, i + 1));
+        }
+        first = false;
+        match (chars.get(i + 1), chars.get(i + 2)) {
+            (Some('-'), Some(&hi)) if hi != ']' => {
+                ranges.push((c, hi));
+                i += 3;
+            }
+            _ => {
+                ranges.push((c, c));
+                i += 1;
+            }
+        }
+    }
+}
+
+/// Returns `true` if `tokens` match all of `s`.
+///
+/// Whether `tokens[t..]` matches `s[i..]` is computed for every `t` and `i`,
+/// from the last token to the first keeping one row per token, so stars
+/// take O(tokens × chars) time rather than backtracking.
+fn matches_tokens(tokens: &[Token], s: &[char]) -> bool {
+    // `next[i]` is whether the tokens after the current one match `s[i..]`
+    let mut next: Vec<bool> = (0..=s.len()).map(|i| i == s.len()).collect();
+    let mut current = vec![false; s.len() + 1];
+    for token in tokens.iter().rev() {
+        // with `Token::AnyDirs`, whether some `/` at or after `i` is
+        // followed by a match of the next tokens
+        let mut after_slash = false;
+        current[s.len()] = match token {
+            Token::Star | Token::AnyPath | Token::AnyDirs => next[s.len()],
+            _ => false,
+        };
+        for i in (0..s.len()).rev() {
+            current[i] = match token {
+                Token::Star => next[i] || (s[i] != '/' && current[i + 1]),
+                Token::AnyPath => next[i] || current[i + 1],
+                Token::AnyDirs => {
+                    // either no directories, or everything up to some `/`
+                    after_slash = after_slash || (s[i] == '/' && next[i + 1]);
+                    next[i] || after_slash
+                }
+                _ => token.matches_char(s[i]) && next[i + 1],
+            };
+        }
+        std::mem::swap(&mut next, &mut current);
+    }
+    next[0]
+}
+
+/// Selects which files are counted with `--include` and `--exclude`
+/// patterns, matched against paths relative to the directory being walked
+/// or as given on the command line.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct PathFilter {
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+}
+
+impl PathFilter {
+    /// Only count files matching `pattern` or another included pattern.
+    pub fn include(&mut self, pattern: &str) {
+        self.include.push(Glob::new(pattern));
+    }
+
+    /// Don't count files matching `pattern`, even if they are included.
+    pub fn exclude(&mut self, pattern: &str) {
+        self.exclude.push(Glob::new(pattern));
+    }
+
+    /// Returns `true` if the file at the relative `path` should be counted.
+    pub fn allows_file(&self, path: &Path) -> bool {
+        !self.exclude.iter().any(|glob| glob.matches(path))
+            && (self.include.is_empty() || self.include.iter().any(|glob| glob.matches(path)))
+    }
+
+    /// Returns `true` if the directory at the relative `path` should be
+    /// walked. Include patterns only apply to files, so only excluded
+    /// directories are skipped.
+    pub fn allows_dir(&self, path: &Path) -> bool {
+        !self.exclude.iter().any(|glob| glob.matches_dir(path))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn glob_matches(pattern: &str, path: &str) -> bool {
+        Glob::new(pattern).matches(Path::new(path))
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("*.rs", "main.rs"));
+        assert!(glob_matches("*.rs", "src/bin/main.rs"));
+        assert!(!glob_matches("*.rs", "main.rs.orig"));
+        assert!(glob_matches("ma?n.rs", "main.rs"));
+        assert!(glob_matches("src/*.rs", "src/lib.rs"));
+        assert!(!glob_matches("src/*.rs", "src/bin/main.rs"));
+        assert!(!glob_matches("src/*.rs", "other/src/lib.rs"));
+        assert!(glob_matches("src/**/*.rs", "src/lib.rs"));
+        assert!(glob_matches("src/**/*.rs", "src/bin/deep/main.rs"));
+        assert!(glob_matches("**/*.rs", "lib.rs"));
+        assert!(glob_matches("target/**", "target/debug/wcrs"));
+        assert!(!glob_matches("target/**", "src/target.rs"));
+        assert!(glob_matches("src/**", "src/a/b"));
+        assert!(glob_matches("a**b/c", "a/x/b/c"));
+    }
+
+    #[test]
+    fn test_glob_many_stars() {
+        // backtracking would try every split of the name between the stars
+        let name = "a".repeat(51) + "c";
+        let pattern = "*a".repeat(10) + "*b";
+        assert!(!glob_matches(&pattern, &name));
+        assert!(glob_matches(&pattern, &(name + "b")));
+        let path = "a/".repeat(30) + "b";
+        assert!(!glob_matches(&"**/a".repeat(10), &path));
+        assert!(glob_matches(&("**/a".repeat(10) + "/**"), &path));
+    }
+
+    #[test]
+    fn test_glob_path_forms() {
+        assert!(glob_matches("src/*.rs", "./src/a.rs"));
+        assert!(glob_matches("src/*.rs", "src/./a.rs"));
+        assert!(glob_matches("*.rs", "./a.rs"));
+        assert!(!glob_matches("src/*.rs", "/src/a.rs"));
+        assert!(glob_matches("/src/*.rs", "/src/a.rs"));
+        assert!(glob_matches("**/src/*.rs", "/home/src/a.rs"));
+        assert!(glob_matches("../*.rs", "../a.rs"));
+    }
+
+    #[test]
+    fn test_glob_classes() {
+        assert!(glob_matches("[abc].txt", "b.txt"));
+        assert!(!glob_matches("[abc].txt", "d.txt"));
+        assert!(glob_matches("[a-c][0-9].txt", "c7.txt"));
+        assert!(glob_matches("[!a-c].txt", "d.txt"));
+        assert!(!glob_matches("[^a-c].txt", "a.txt"));
+        assert!(glob_matches("[]x].txt", "].txt"));
+        assert!(glob_matches("[a-].txt", "-.txt"));
+        // unclosed classes and escapes are literal
+        assert!(glob_matches("[ab", "[ab"));
+        assert!(glob_matches("\\*.txt", "*.txt"));
+        assert!(!glob_matches("\\*.txt", "a.txt"));
+    }
+
+    #[test]
+    fn test_glob_anchored() {
+        assert!(Glob::anchored("target").matches(Path::new("target")));
+        assert!(!Glob::anchored("target").matches(Path::new("a/target")));
+    }
+
+    #[test]
+    fn test_glob_matches_dir() {
+        assert!(Glob::new("target/**").matches_dir(Path::new("target")));
+        assert!(Glob::new("target").matches_dir(Path::new("a/target")));
+        assert!(!Glob::new("target/**").matches_dir(Path::new("src")));
+    }
+
+    #[test]
+    fn test_path_filter() {
+        let mut filter = PathFilter::default();
+        assert!(filter.allows_file(Path::new("anything")));
+
+        filter.include("*.rs");
+        filter.include("*.toml");
+        filter.exclude("target/**");
+        filter.exclude("generated_*");
+        assert!(filter.allows_file(Path::new("src/main.rs")));
+        assert!(filter.allows_file(Path::new("Cargo.toml")));
+        assert!(!filter.allows_file(Path::new("README.md")));
+        assert!(!filter.allows_file(Path::new("target/debug/build.rs")));
+        assert!(!filter.allows_file(Path::new("src/generated_parser.rs")));
+
+        assert!(filter.allows_dir(Path::new("src")));
+        assert!(!filter.allows_dir(Path::new("target")));
+    }
+}
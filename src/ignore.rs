@@ -0,0 +1,177 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::glob::Glob;
+
+/// The names of the ignore files read from each directory, from lowest to
+/// highest precedence.
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".ignore"];
+
+/// The ignore file of a git repository that is not checked in, relative to
+/// the root of the repository.
+const GIT_EXCLUDE: &str = ".git/info/exclude";
+
+/// A single line of an ignore file.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct IgnoreRule {
+    glob: Glob,
+    /// whether the rule starts with `!`, so matching paths are no longer
+    /// ignored
+    negated: bool,
+    /// whether the rule ends with `/`, so it only matches directories
+    dir_only: bool,
+}
+
+impl IgnoreRule {
+    /// Parse a line of an ignore file, returning `None` for blank lines and
+    /// comments.
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.starts_with('#') {
+            return None;
+        }
+        let (negated, pattern) = match line.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, line),
+        };
+        let pattern = trim_trailing_spaces(pattern);
+        let (dir_only, pattern) = match pattern.strip_suffix('/') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern),
+        };
+        if pattern.is_empty() {
+            return None;
+        }
+        // a `/` anywhere but the end anchors the pattern to the directory of
+        // the ignore file, otherwise it matches a name at any depth
+        let glob = if pattern.contains('/') {
+            Glob::anchored(pattern.strip_prefix('/').unwrap_or(pattern))
+        } else {
+            Glob::new(pattern)
+        };
+        Some(Self {
+            glob,
+            negated,
+            dir_only,
+        })
+    }
+}
+
+/// Remove the spaces at the end of `pattern`, except for one escaped with `\`.
+fn trim_trailing_spaces(pattern: &str) -> &str {
+    let mut end = pattern.len();
+    while pattern[..end].ends_with(' ') && !pattern[..end - 1].ends_with('\\') {
+        end -= 1;
+    }
+    &pattern[..end]
+}
+
+/// The rules from the ignore files of a single directory, in the syntax of
+/// `.gitignore` files: blank lines and lines starting with `#` are skipped,
+/// `!` negates a rule, a trailing `/` only matches directories, and a
+/// leading or middle `/` anchors a rule to the directory. When several
+/// rules match a path the last one wins.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Ignore {
+    rules: Vec<IgnoreRule>,
+}
+
+impl Ignore {
+    /// Parse the contents of an ignore file.
+    pub fn parse(text: &str) -> Self {
+        Self {
+            rules: text.lines().filter_map(IgnoreRule::parse).collect(),
+        }
+    }
+
+    /// Read the rules of the ignore files in `dir`, see `IGNORE_FILES`.
+    /// Files that don't exist are skipped.
+    pub fn for_dir(dir: &Path) -> io::Result<Self> {
+        let mut ignore = Self::default();
+        for name in IGNORE_FILES {
+            match fs::read(dir.join(name)) {
+                Ok(bytes) => ignore
+                    .rules
+                    .extend(Self::parse(&String::from_utf8_lossy(&bytes)).rules),
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::NotFound | io::ErrorKind::NotADirectory
+                    ) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(ignore)
+    }
+
+    /// Read the rules of `.git/info/exclude` in the root directory of a git
+    /// repository. The file is skipped if it can't be read for any reason,
+    /// since in a worktree or submodule `.git` is a file rather than a
+    /// directory.
+    pub fn for_repository(root: &Path) -> Self {
+        fs::read(root.join(GIT_EXCLUDE))
+            .map(|bytes| Self::parse(&String::from_utf8_lossy(&bytes)))
+            .unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Returns `Some(true)` if `path`, relative to the directory of the
+    /// rules, is ignored, `Some(false)` if a negated rule says it isn't,
+    /// and `None` if no rule matches it.
+    pub fn matched(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| (is_dir || !rule.dir_only) && rule.glob.matches(path))
+            .map(|rule| !rule.negated)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ignored(ignore: &Ignore, path: &str, is_dir: bool) -> Option<bool> {
+        ignore.matched(Path::new(path), is_dir)
+    }
+
+    #[test]
+    fn test_parse() {
+        let ignore = Ignore::parse("# a comment\n\n  \n\\#not-a-comment\r\n/target\n");
+        assert_eq!(ignore.rules.len(), 2);
+        assert_eq!(ignored(&ignore, "#not-a-comment", false), Some(true));
+        assert_eq!(trim_trailing_spaces("a  "), "a");
+        assert_eq!(trim_trailing_spaces("a\\  "), "a\\ ");
+    }
+
+    #[test]
+    fn test_matched() {
+        let ignore = Ignore::parse("*.log\n!keep.log\nbuild/\n/dist\ndocs/*.html\n\\!bang\n");
+        assert_eq!(ignored(&ignore, "a.log", false), Some(true));
+        assert_eq!(ignored(&ignore, "src/debug.log", false), Some(true));
+        assert_eq!(ignored(&ignore, "src/keep.log", false), Some(false));
+        assert_eq!(ignored(&ignore, "main.rs", false), None);
+        // directory only rules
+        assert_eq!(ignored(&ignore, "build", true), Some(true));
+        assert_eq!(ignored(&ignore, "src/build", true), Some(true));
+        assert_eq!(ignored(&ignore, "build", false), None);
+        // anchored rules
+        assert_eq!(ignored(&ignore, "dist", true), Some(true));
+        assert_eq!(ignored(&ignore, "src/dist", true), None);
+        assert_eq!(ignored(&ignore, "docs/index.html", false), Some(true));
+        assert_eq!(ignored(&ignore, "src/docs/index.html", false), None);
+        assert_eq!(ignored(&ignore, "!bang", false), Some(true));
+    }
+
+    #[test]
+    fn test_last_rule_wins() {
+        let ignore = Ignore::parse("!important.txt\n*.txt\n");
+        assert_eq!(ignored(&ignore, "important.txt", false), Some(true));
+        let ignore = Ignore::parse("*.txt\n!important.txt\n");
+        assert_eq!(ignored(&ignore, "important.txt", false), Some(false));
+    }
+}
@@ -0,0 +1,150 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek};
+use std::path::PathBuf;
+
+use memmap2::{Mmap, MmapOptions};
+
+/// Return the number of bytes left to read from `file` if it is known
+/// without reading it, which is only the case for a regular file that
+/// reports a non zero size. Returns `None` for pipes, devices, and files
+/// such as those in procfs that report a size of 0.
+pub fn size_from_metadata(file: &File) -> Option<u64> {
+    let metadata = file.metadata().ok()?;
+    if !metadata.is_file() || metadata.len() == 0 {
+        return None;
+    }
+    // only count what is left after the current position, in case the file
+    // was opened by someone else and partially read already
+    let mut file = file;
+    let position = file.stream_position().ok()?;
+    Some(metadata.len().saturating_sub(position))
+}
+
+/// Memory map the rest of `file` from its current position, returning
+/// `None` if it is not a regular file with a non zero size or mapping it
+/// fails, in which case it should be read instead.
+///
+/// The map is only valid while nothing else modifies the file. Reading a
+/// page of the map after the file is truncated raises `SIGBUS`, which kills
+/// the process, and a file rewritten while it is mapped is undefined
+/// behavior, so this is only used with `--mmap`, which opts in to these
+/// risks.
+pub fn map_file(file: &File) -> Option<Mmap> {
+    let size = size_from_metadata(file)?;
+    let mut position_file = file;
+    let offset = position_file.stream_position().ok()?;
+    let len = usize::try_from(size).ok()?;
+    if len == 0 {
+        return None;
+    }
+    // SAFETY: this is only sound as long as no other process truncates or
+    // writes to the file while it is mapped, which can't be prevented, so
+    // the caller has to accept the risk, see the doc comment
+    unsafe { MmapOptions::new().offset(offset).len(len).map(file) }.ok()
+}
+
+/// Read a list of NUL terminated file names from `reader`, as given to
+/// `--files0-from`. The final name does not need to be terminated. Empty
+/// names are kept so they can be reported.
+pub fn read_files0<R: Read>(reader: R) -> io::Result<Vec<PathBuf>> {
+    BufReader::new(reader)
+        .split(b'\0')
+        .map(|name| name.map(path_from_bytes))
+        .collect()
+}
+
+/// Convert a file name read as raw bytes to a path.
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    PathBuf::from(std::ffi::OsString::from_vec(bytes))
+}
+
+/// Convert a file name read as raw bytes to a path.
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Return a `File` referring to the same open file as `stdin`, so its
+/// metadata can be inspected.
+#[cfg(unix)]
+pub fn stdin_file() -> Option<File> {
+    use std::os::fd::AsFd;
+    let fd = std::io::stdin().as_fd().try_clone_to_owned().ok()?;
+    Some(File::from(fd))
+}
+
+/// Return a `File` referring to the same open file as `stdin`, so its
+/// metadata can be inspected.
+#[cfg(not(unix))]
+pub fn stdin_file() -> Option<File> {
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::SeekFrom;
+
+    #[test]
+    fn test_size_from_metadata() {
+        let path = std::env::temp_dir().join(format!("wcrs-size-{}", std::process::id()));
+        std::fs::write(&path, "0123456789").unwrap();
+        let mut file = File::open(&path).unwrap();
+        assert_eq!(size_from_metadata(&file), Some(10));
+
+        // only the bytes after the current position are counted
+        let mut buf = [0u8; 4];
+        file.read_exact(&mut buf).unwrap();
+        assert_eq!(size_from_metadata(&file), Some(6));
+        file.seek(SeekFrom::End(0)).unwrap();
+        assert_eq!(size_from_metadata(&file), Some(0));
+
+        // empty files may be special files which report a size of 0
+        std::fs::write(&path, "").unwrap();
+        assert_eq!(size_from_metadata(&File::open(&path).unwrap()), None);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_size_from_metadata_special() {
+        // procfs files report a size of 0 even though they have contents
+        let file = File::open("/proc/self/status").unwrap();
+        assert_eq!(size_from_metadata(&file), None);
+        let file = File::open("/dev/null").unwrap();
+        assert_eq!(size_from_metadata(&file), None);
+    }
+
+    #[test]
+    fn test_map_file() {
+        let path = std::env::temp_dir().join(format!("wcrs-map-{}", std::process::id()));
+        std::fs::write(&path, "0123456789").unwrap();
+        let mut file = File::open(&path).unwrap();
+        assert_eq!(&map_file(&file).unwrap()[..], b"0123456789");
+        file.seek(SeekFrom::Start(3)).unwrap();
+        assert_eq!(&map_file(&file).unwrap()[..], b"3456789");
+        file.seek(SeekFrom::End(0)).unwrap();
+        assert!(map_file(&file).is_none());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_read_files0() {
+        let names = read_files0(&b"a\0dir/b c\0\0d\ne\0"[..]).unwrap();
+        assert_eq!(
+            names,
+            vec![
+                PathBuf::from("a"),
+                PathBuf::from("dir/b c"),
+                PathBuf::from(""),
+                PathBuf::from("d\ne"),
+            ]
+        );
+        // the last name doesn't need a terminating NUL
+        let names = read_files0(&b"a\0b"[..]).unwrap();
+        assert_eq!(names, vec![PathBuf::from("a"), PathBuf::from("b")]);
+        assert!(read_files0(&b""[..]).unwrap().is_empty());
+    }
+}
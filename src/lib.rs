@@ -1,4 +1,19 @@
 pub mod cli_args;
+pub mod code_stats;
 pub mod constants;
+pub mod count_options;
+pub mod counter;
 pub mod display_options;
+pub mod distinct;
+pub mod extras;
 pub mod file_result;
+pub mod glob;
+pub mod ignore;
+pub mod input;
+pub mod newlines;
+pub mod output;
+pub mod parallel;
+pub mod partial;
+pub mod walk;
+pub mod width;
+pub mod word_freq;
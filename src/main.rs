@@ -1,85 +1,332 @@
+use std::env;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
-use std::{env, io::Read};
-use wcrs::cli_args::parse_args;
+use wcrs::cli_args::{parse_args, CliArgs};
+use wcrs::code_stats::{language_for_path, Language, LanguageTotals};
 use wcrs::constants::{EXIT_FAILURE, EXIT_SUCCESS, PROGRAM};
-use wcrs::file_result::{counts_for_file, file_result_string, FileResult};
-
-/// Reads `file` to a string return either the string or an `std::io::Error`
-/// if something failed.
-fn read_file<F: Read>(file: &mut F) -> Result<String, std::io::Error> {
-    // TODO: what is faster?
-    // let mut bytes = Vec::new();
-    // file.read_to_end(&mut bytes);
-    let mut contents = String::with_capacity(256);
-    match file.read_to_string(&mut contents) {
-        Ok(_) => Ok(contents),
-        Err(e) => Err(e),
+use wcrs::counter::counts_for_reader_with_options;
+use wcrs::display_options::{num_to_display, DisplayOptions};
+use wcrs::extras::{counts_and_extras_for_mapped_file, counts_and_extras_for_reader, Extras};
+use wcrs::file_result::{column_width, counts_for_mapped_file, FileResult};
+use wcrs::input::{read_files0, size_from_metadata, stdin_file};
+use wcrs::output::Printer;
+use wcrs::parallel::{counts_for_path_split, for_each_ordered};
+use wcrs::walk::{walk, Walk};
+use wcrs::word_freq::WordCounts;
+
+/// If only the byte count is displayed and `file` is a regular file, return
+/// a `FileResult` with the size of the file from its metadata so that it
+/// does not need to be read.
+fn bytes_from_metadata(file: &std::fs::File, options: &DisplayOptions) -> Option<FileResult> {
+    if *options != DisplayOptions::with_bytes_only() {
+        return None;
     }
+    let bytes = size_from_metadata(file)?;
+    Some(FileResult::new(0, 0, 0, bytes as usize))
+}
+
+/// Computes counts for everything read from `reader` along with the extras
+/// requested in `cli_args` in a single pass. `language` is the language of
+/// the input for `--code-stats`, if it is known.
+fn count_with_extras<R: Read>(
+    reader: &mut R,
+    cli_args: &CliArgs,
+    language: Option<&'static Language>,
+) -> Result<(FileResult, Extras), std::io::Error> {
+    counts_and_extras_for_reader(
+        reader,
+        &cli_args.display_options,
+        &cli_args.count_options,
+        language,
+        cli_args.word_freq.as_ref(),
+    )
 }
 
 /// Computes counts for the `stdin` stream return either the computed
-/// `FileResult` or a `std::io::error`.
-fn process_stdin() -> Result<FileResult, std::io::Error> {
+/// `FileResult` and the extras requested in `cli_args`, or a
+/// `std::io::error`. Only the counts needed for the display options in
+/// `cli_args` are computed.
+fn process_stdin(cli_args: &CliArgs) -> Result<(FileResult, Extras), std::io::Error> {
+    let options = &cli_args.display_options;
     let mut stdinlock = std::io::stdin().lock();
-    match read_file(&mut stdinlock) {
-        Ok(contents) => Ok(counts_for_file(&contents)),
-        Err(e) => Err(e),
+    if cli_args.word_freq.is_some() {
+        return count_with_extras(&mut stdinlock, cli_args, None);
+    }
+    if let Some(result) = stdin_file().and_then(|file| bytes_from_metadata(&file, options)) {
+        return Ok((result, Extras::default()));
+    }
+    let result = counts_for_reader_with_options(&mut stdinlock, options, &cli_args.count_options)?;
+    Ok((result, Extras::default()))
+}
+
+/// Computes counts for the file at `path` returning either the computed
+/// `FileResult` or a `std::io::Error` if the file could not be opened or read.
+/// Only the counts needed for the display options in `cli_args` are computed.
+/// Regular files are split into byte ranges counted on separate threads
+/// with `--split-files`, and memory mapped with `--mmap`.
+fn process_path(path: &Path, cli_args: &CliArgs) -> Result<FileResult, std::io::Error> {
+    let options = &cli_args.display_options;
+    let mut file = std::fs::OpenOptions::new().read(true).open(path)?;
+    if let Some(result) = bytes_from_metadata(&file, options) {
+        return Ok(result);
+    }
+    // partial results can't compute the width of lines, grapheme clusters
+    // or line endings, find words by anything but whitespace, or end lines
+    // at anything but line feeds
+    if cli_args.split_files
+        && cli_args.jobs > 1
+        && options.splittable()
+        && cli_args.count_options.splittable()
+        && file.metadata()?.is_file()
+    {
+        return counts_for_path_split(path, cli_args.jobs, options);
+    }
+    if cli_args.mmap {
+        return counts_for_mapped_file(&mut file, options, &cli_args.count_options);
+    }
+    counts_for_reader_with_options(&mut file, options, &cli_args.count_options)
+}
+
+/// Computes counts for the file at `path` like `process_path`, along with
+/// the extras requested in `cli_args`. Code statistics are only computed
+/// for files whose language is known. The extras need the contents of the
+/// file in order, so a file with any is never split into byte ranges or
+/// counted from its metadata, but it is still memory mapped with `--mmap`.
+fn process_path_with_extras(
+    path: &Path,
+    cli_args: &CliArgs,
+) -> Result<(FileResult, Extras), std::io::Error> {
+    let language = cli_args
+        .code_stats
+        .then(|| language_for_path(path))
+        .flatten();
+    if language.is_none() && cli_args.word_freq.is_none() {
+        return Ok((process_path(path, cli_args)?, Extras::default()));
+    }
+    let mut file = std::fs::File::open(path)?;
+    if cli_args.mmap {
+        return counts_and_extras_for_mapped_file(
+            &mut file,
+            &cli_args.display_options,
+            &cli_args.count_options,
+            language,
+            cli_args.word_freq.as_ref(),
+        );
     }
+    count_with_extras(&mut file, cli_args, language)
+}
+
+/// Add the `extras` of an input to the code statistics `languages` and the
+/// word counts `words` of all inputs.
+fn add_extras(extras: Extras, languages: &mut LanguageTotals, words: &mut WordCounts) {
+    if let Some((language, stats)) = extras.code_stats {
+        languages.add(language, &stats);
+    }
+    if let Some(word_counts) = extras.word_counts {
+        words.merge(word_counts);
+    }
+}
+
+/// Reads the NUL terminated paths listed in the `--files0-from` file `from`,
+/// where `-` is stdin. Invalid names are reported and skipped. Returns the
+/// valid paths and whether any names were invalid, or a `std::io::Error` if
+/// `from` could not be read.
+fn process_files0_from(from: &Path) -> Result<(Vec<PathBuf>, bool), std::io::Error> {
+    let from_stdin = from == Path::new("-");
+    let names = if from_stdin {
+        read_files0(std::io::stdin().lock())?
+    } else {
+        read_files0(std::fs::File::open(from)?)?
+    };
+
+    let mut invalid = false;
+    let mut paths = Vec::with_capacity(names.len());
+    for (i, name) in names.into_iter().enumerate() {
+        if name.as_os_str().is_empty() {
+            eprintln!(
+                "{PROGRAM}: {}:{}: invalid zero-length file name",
+                &from.to_string_lossy(),
+                i + 1
+            );
+            invalid = true;
+        } else if from_stdin && name == Path::new("-") {
+            eprintln!(
+                "{PROGRAM}: when reading file names from standard input, no file name of '-' allowed"
+            );
+            invalid = true;
+        } else {
+            paths.push(name);
+        }
+    }
+    Ok((paths, invalid))
+}
+
+/// Returns the size of `metadata` if it is a regular file and `None` for
+/// other kinds of input.
+fn regular_size(metadata: std::fs::Metadata) -> Option<u64> {
+    metadata.is_file().then_some(metadata.len())
 }
 
 fn main() -> ExitCode {
     let args: Vec<String> = env::args().collect();
 
     // TODO: args[1..] is not a good idea
-    let (display_options, paths, read_stdin) = parse_args(&args[1..]);
+    let cli_args = parse_args(&args[1..]);
+    let display_options = &cli_args.display_options;
+    let mut read_stdin = cli_args.read_stdin;
 
     let mut return_exit_failure = false;
-    let mut total = FileResult::default();
-    let print_total = paths.len() > 1 || (paths.len() == 1 && read_stdin);
-    for path in paths {
-        let mut file = match std::fs::OpenOptions::new().read(true).open(&path) {
-            Ok(f) => f,
+    let mut paths = match &cli_args.files0_from {
+        Some(from) => match process_files0_from(from) {
+            Ok((paths, invalid)) => {
+                return_exit_failure = invalid;
+                paths
+            }
             Err(e) => {
-                eprintln!("{PROGRAM}: {}: {}", &path.to_string_lossy(), &e);
-                return_exit_failure = true;
-                continue;
+                eprintln!(
+                    "{PROGRAM}: cannot open '{}' for reading: {}",
+                    &from.to_string_lossy(),
+                    &e
+                );
+                return ExitCode::from(EXIT_FAILURE);
             }
-        };
+        },
+        None => cli_args.paths.clone(),
+    };
+    // a `-` listed in a `--files0-from` file means stdin
+    if paths.iter().any(|path| path == Path::new("-")) {
+        paths.retain(|path| path != Path::new("-"));
+        read_stdin = true;
+    }
 
-        let contents = match read_file(&mut file) {
-            Ok(contents) => contents,
-            Err(e) => {
-                eprintln!("{PROGRAM}: {}: {}", &path.to_string_lossy(), &e);
-                return_exit_failure = true;
-                continue;
+    // replace directories with the files in them, sorted so the output does
+    // not depend on the order the file system lists them in
+    let filter = &cli_args.filter;
+    let mut walk_errors = Vec::new();
+    if cli_args.recursive {
+        let mut found = Walk::default();
+        for path in paths {
+            if path.is_dir() {
+                walk(&path, &cli_args.walk_options, filter, &mut found);
+            } else if filter.allows_file(&path) {
+                found.files.push(path);
             }
-        };
+        }
+        paths = found.files;
+        walk_errors = found.errors;
+    } else {
+        paths.retain(|path| filter.allows_file(path));
+    }
 
-        // compute the counts for the file and accumulate in total
-        let result = counts_for_file(&contents);
-        total.add_mut(&result);
+    let mut total = FileResult::default();
+    let num_inputs = paths.len() + usize::from(read_stdin);
+    let print_total = cli_args.total.print_total(num_inputs);
+    let print_inputs = cli_args.total.print_inputs();
 
-        println!(
-            " {}  {}",
-            file_result_string(&result, &display_options),
-            &path.to_string_lossy()
-        );
+    // a single count for a single input needs no padding, and like GNU wc
+    // the inputs are not inspected when their names are read from stdin,
+    // otherwise the width of the count columns comes from the sizes of the
+    // inputs that can be inspected
+    let names_from_stdin = cli_args.files0_from.as_deref() == Some(Path::new("-"));
+    let width = if names_from_stdin || (num_inputs == 1 && num_to_display(display_options) == 1) {
+        1
+    } else {
+        let mut sizes: Vec<Option<u64>> = paths
+            .iter()
+            .filter_map(|path| std::fs::metadata(path).ok())
+            .map(regular_size)
+            .collect();
+        if read_stdin {
+            sizes.extend(
+                stdin_file()
+                    .and_then(|file| file.metadata().ok())
+                    .map(regular_size),
+            );
+        }
+        column_width(&sizes)
+    };
+
+    let mut languages = LanguageTotals::default();
+    let mut words = WordCounts::default();
+    let mut printer = Printer::new(cli_args.format, display_options, width);
+    if let Some(line) = printer.header() {
+        println!("{}", line);
     }
 
+    for (path, e) in &walk_errors {
+        eprintln!("{PROGRAM}: {}: {}", &path.to_string_lossy(), e);
+        if let Some(line) = printer.error(&path.to_string_lossy(), e) {
+            println!("{}", line);
+        }
+        return_exit_failure = true;
+    }
+
+    // files are counted on `jobs` threads but their results are printed in
+    // the order they were given, unless each file is being split across
+    // the threads instead
+    let file_jobs = if cli_args.split_files {
+        1
+    } else {
+        cli_args.jobs
+    };
+    for_each_ordered(
+        &paths,
+        file_jobs,
+        |path| process_path_with_extras(path, &cli_args),
+        |path, result| match result {
+            Ok((result, extras)) => {
+                // accumulate the counts for the file in total
+                total.add_mut(&result);
+                add_extras(extras, &mut languages, &mut words);
+                if print_inputs {
+                    if let Some(line) = printer.result(Some(&path.to_string_lossy()), &result) {
+                        println!("{}", line);
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("{PROGRAM}: {}: {}", &path.to_string_lossy(), &e);
+                if let Some(line) = printer.error(&path.to_string_lossy(), &e) {
+                    println!("{}", line);
+                }
+                return_exit_failure = true;
+            }
+        },
+    );
+
     if read_stdin {
-        match process_stdin() {
-            Ok(result) => {
+        match process_stdin(&cli_args) {
+            Ok((result, extras)) => {
                 total.add_mut(&result);
-                println!(" {}  -", file_result_string(&result, &display_options),);
+                add_extras(extras, &mut languages, &mut words);
+                if print_inputs {
+                    // like GNU wc, stdin only has a name if it was asked for
+                    let name = (!cli_args.implicit_stdin).then_some("-");
+                    if let Some(line) = printer.result(name, &result) {
+                        println!("{}", line);
+                    }
+                }
             }
             Err(e) => {
                 eprintln!("{PROGRAM}: -: {}", &e);
+                if let Some(line) = printer.error("-", &e) {
+                    println!("{}", line);
+                }
             }
         }
     }
 
-    if print_total {
-        println!(" {}  total", file_result_string(&total, &display_options),);
+    if cli_args.code_stats {
+        printer.set_code_stats(languages);
+    }
+    if let Some(word_freq) = &cli_args.word_freq {
+        printer.set_word_freq(words.top(word_freq.top));
+    }
+    // with `--total=only` there are no other lines to tell the total apart from
+    let total = print_total.then_some(&total);
+    if let Some(line) = printer.footer(total, print_inputs) {
+        println!("{}", line);
     }
 
     if return_exit_failure {
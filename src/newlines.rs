@@ -0,0 +1,87 @@
+/// Count the newline bytes in `bytes` without decoding any characters.
+/// Uses SSE2 on x86_64, and compares 8 bytes at a time elsewhere.
+pub fn count_newlines(bytes: &[u8]) -> usize {
+    #[cfg(target_arch = "x86_64")]
+    {
+        count_newlines_sse2(bytes)
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        count_newlines_swar(bytes)
+    }
+}
+
+/// Count the newline bytes in `bytes` 16 bytes at a time using SSE2, which
+/// every x86_64 CPU supports.
+#[cfg(target_arch = "x86_64")]
+fn count_newlines_sse2(bytes: &[u8]) -> usize {
+    use std::arch::x86_64::{
+        __m128i, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_movemask_epi8, _mm_set1_epi8,
+    };
+
+    let mut chunks = bytes.chunks_exact(16);
+    let mut count = 0;
+    // SAFETY: SSE2 is always enabled on x86_64, every `chunk` is exactly 16
+    // bytes long, and `_mm_loadu_si128` does not require alignment
+    unsafe {
+        let newlines = _mm_set1_epi8(b'\n' as i8);
+        for chunk in &mut chunks {
+            let v = _mm_loadu_si128(chunk.as_ptr() as *const __m128i);
+            // one bit per byte of `v` that is a newline
+            let mask = _mm_movemask_epi8(_mm_cmpeq_epi8(v, newlines));
+            count += mask.count_ones() as usize;
+        }
+    }
+    count + count_newlines_swar(chunks.remainder())
+}
+
+/// Count the newline bytes in `bytes` 8 bytes at a time by treating each
+/// group of bytes as a `u64`.
+fn count_newlines_swar(bytes: &[u8]) -> usize {
+    const ONES: u64 = 0x0101_0101_0101_0101;
+    const HIGH_BITS: u64 = 0x8080_8080_8080_8080;
+    const NEWLINES: u64 = ONES * b'\n' as u64;
+
+    let mut chunks = bytes.chunks_exact(8);
+    let mut count = 0;
+    for chunk in &mut chunks {
+        let word = u64::from_ne_bytes(chunk.try_into().expect("chunk is 8 bytes"));
+        // the bytes that were newlines are now zero
+        let x = word ^ NEWLINES;
+        // set the high bit of every non zero byte without carrying into the
+        // next byte, then flip it so only the zero bytes have it set
+        let non_zero = (((x & !HIGH_BITS) + !HIGH_BITS) | x) & HIGH_BITS;
+        count += (non_zero ^ HIGH_BITS).count_ones() as usize;
+    }
+    count + chunks.remainder().iter().filter(|&&b| b == b'\n').count()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn naive_count(bytes: &[u8]) -> usize {
+        bytes.iter().filter(|&&b| b == b'\n').count()
+    }
+
+    #[test]
+    fn test_count_newlines() {
+        let mut bytes: Vec<u8> = (0..=255u8).cycle().take(1000).collect();
+        bytes.extend_from_slice(b"\n\n\n\na\nb\n\n");
+        // check every alignment and remainder length
+        for start in 0..20 {
+            for end in (bytes.len() - 20)..=bytes.len() {
+                let slice = &bytes[start..end];
+                assert_eq!(count_newlines(slice), naive_count(slice));
+                assert_eq!(count_newlines_swar(slice), naive_count(slice));
+            }
+        }
+        assert_eq!(count_newlines(b""), 0);
+        assert_eq!(count_newlines(&[b'\n'; 100]), 100);
+        // bytes next to a newline must not be miscounted
+        assert_eq!(
+            count_newlines_swar(&[0x0b, 0x8a, 0x09, 0x0a, 0x8b, 0, 0xff, 0x0a]),
+            2
+        );
+    }
+}
@@ -0,0 +1,516 @@
+use std::io;
+
+use crate::code_stats::LanguageTotals;
+use crate::display_options::DisplayOptions;
+use crate::file_result::{displayed_counts, displayed_names, file_result_string, FileResult};
+
+/// The format counts are printed in.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum OutputFormat {
+    /// whitespace separated columns like GNU wc
+    #[default]
+    Text,
+    /// a JSON object with an entry for each input and the total
+    Json,
+    /// comma separated values with a header row
+    Csv,
+    /// tab separated values with a header row
+    Tsv,
+}
+
+/// Builds the lines to print for the counts of each input and the total
+/// in an `OutputFormat`. Only the counts turned on in the display options
+/// are included.
+///
+/// The JSON format is a single object of the form
+/// `{"inputs":[{"path":"a","lines":1},...],"total":{"lines":1}}`, printed
+/// with one input per line. An input that could not be counted has an
+/// `"error"` instead of counts, and `"total"` is left out when no total is
+/// printed.
+///
+/// The CSV and TSV formats have a header row naming the columns: `type`,
+/// which is `input` or `total`, `path`, and then the counts. Inputs that
+/// could not be counted are only reported on stderr.
+///
+/// With `--code-stats`, the text format ends with a table of the code
+/// statistics of each language, and the JSON format has a `"languages"`
+/// array. Likewise with `--word-freq` for the most frequent words and the
+/// `"word_freq"` array. The CSV and TSV formats have no place for them.
+#[derive(Debug, Clone)]
+pub struct Printer {
+    format: OutputFormat,
+    options: DisplayOptions,
+    /// the width of the count columns in the text format
+    width: usize,
+    /// a JSON entry that can't be printed until we know if another entry
+    /// follows it
+    pending: Option<String>,
+    /// the code statistics to print after the total
+    code_stats: Option<LanguageTotals>,
+    /// the most frequent words and their counts to print after the total
+    word_freq: Option<Vec<(String, usize)>>,
+}
+
+impl Printer {
+    pub fn new(format: OutputFormat, options: &DisplayOptions, width: usize) -> Self {
+        Self {
+            format,
+            options: options.clone(),
+            width,
+            pending: None,
+            code_stats: None,
+            word_freq: None,
+        }
+    }
+
+    /// Print the per language code statistics `totals` in the footer.
+    pub fn set_code_stats(&mut self, totals: LanguageTotals) {
+        self.code_stats = Some(totals);
+    }
+
+    /// Print the most frequent words `words` with their counts in the footer.
+    pub fn set_word_freq(&mut self, words: Vec<(String, usize)>) {
+        self.word_freq = Some(words);
+    }
+
+    /// The line to print before any counts, if there is one.
+    pub fn header(&self) -> Option<String> {
+        match self.format {
+            OutputFormat::Text => None,
+            OutputFormat::Json => Some(String::from("{\"inputs\":[")),
+            OutputFormat::Csv | OutputFormat::Tsv => {
+                let names = displayed_names(&self.options);
+                let header = ["type", "path"].into_iter().chain(names);
+                Some(self.delimited_row(header.map(String::from)))
+            }
+        }
+    }
+
+    /// The line to print for the counts `result` of the input called
+    /// `name`, where `name` is `None` for stdin read because no inputs were
+    /// given. Returns `None` if nothing can be printed yet.
+    pub fn result(&mut self, name: Option<&str>, result: &FileResult) -> Option<String> {
+        match self.format {
+            OutputFormat::Text => {
+                let counts = file_result_string(result, &self.options, self.width);
+                match name {
+                    Some(name) => Some(format!("{} {}", counts, name)),
+                    None => Some(counts),
+                }
+            }
+            OutputFormat::Json => {
+                let entry = format!(
+                    "{{\"path\":{},{}}}",
+                    json_string(name.unwrap_or("-")),
+                    self.json_counts(result)
+                );
+                self.push_entry(entry)
+            }
+            OutputFormat::Csv | OutputFormat::Tsv => {
+                Some(self.counts_row("input", name.unwrap_or("-"), result))
+            }
+        }
+    }
+
+    /// The line to print for the input called `name` which could not be
+    /// counted because of `error`. Returns `None` if nothing can be printed
+    /// yet, or the format has no place for errors, which are always also
+    /// printed to stderr.
+    pub fn error(&mut self, name: &str, error: &io::Error) -> Option<String> {
+        match self.format {
+            OutputFormat::Text | OutputFormat::Csv | OutputFormat::Tsv => None,
+            OutputFormat::Json => {
+                let entry = format!(
+                    "{{\"path\":{},\"error\":{}}}",
+                    json_string(name),
+                    json_string(&error.to_string())
+                );
+                self.push_entry(entry)
+            }
+        }
+    }
+
+    /// The lines to print after all inputs, including the `total` counts if
+    /// they are printed. `named` is whether the total is labelled as such
+    /// in the text format.
+    pub fn footer(&mut self, total: Option<&FileResult>, named: bool) -> Option<String> {
+        match self.format {
+            OutputFormat::Text => {
+                let mut lines: Vec<String> = total
+                    .map(|total| {
+                        let counts = file_result_string(total, &self.options, self.width);
+                        if named {
+                            format!("{} total", counts)
+                        } else {
+                            counts
+                        }
+                    })
+                    .into_iter()
+                    .collect();
+                if let Some(totals) = &self.code_stats {
+                    lines.extend(code_stats_table(totals));
+                }
+                if let Some(words) = &self.word_freq {
+                    lines.extend(word_freq_table(words));
+                }
+                (!lines.is_empty()).then(|| lines.join("\n"))
+            }
+            OutputFormat::Json => {
+                let mut s = self
+                    .pending
+                    .take()
+                    .map(|entry| entry + "\n")
+                    .unwrap_or_default();
+                s.push(']');
+                if let Some(total) = total {
+                    s.push_str(&format!(",\"total\":{{{}}}", self.json_counts(total)));
+                }
+                if let Some(totals) = &self.code_stats {
+                    s.push_str(&format!(",\"languages\":[{}]", code_stats_json(totals)));
+                }
+                if let Some(words) = &self.word_freq {
+                    s.push_str(&format!(",\"word_freq\":[{}]", word_freq_json(words)));
+                }
+                s.push('}');
+                Some(s)
+            }
+            OutputFormat::Csv | OutputFormat::Tsv => {
+                total.map(|total| self.counts_row("total", "", total))
+            }
+        }
+    }
+
+    /// Hold back `entry` until the next one, returning the entry before it
+    /// followed by a separator.
+    fn push_entry(&mut self, entry: String) -> Option<String> {
+        self.pending.replace(entry).map(|previous| previous + ",")
+    }
+
+    /// The displayed counts of `result` as the members of a JSON object.
+    fn json_counts(&self, result: &FileResult) -> String {
+        displayed_names(&self.options)
+            .into_iter()
+            .zip(displayed_counts(result, &self.options))
+            .map(|(name, count)| format!("\"{}\":{}", name, count))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// A CSV or TSV row of the given `kind` for the counts `result` of the
+    /// input called `name`.
+    fn counts_row(&self, kind: &str, name: &str, result: &FileResult) -> String {
+        let counts = displayed_counts(result, &self.options);
+        let fields = [kind.to_string(), name.to_string()]
+            .into_iter()
+            .chain(counts.into_iter().map(|count| count.to_string()));
+        self.delimited_row(fields)
+    }
+
+    /// Join `fields` into a CSV or TSV row, escaping them as needed.
+    fn delimited_row<I: Iterator<Item = String>>(&self, fields: I) -> String {
+        if self.format == OutputFormat::Tsv {
+            fields
+                .map(|field| tsv_field(&field))
+                .collect::<Vec<_>>()
+                .join("\t")
+        } else {
+            fields
+                .map(|field| csv_field(&field))
+                .collect::<Vec<_>>()
+                .join(",")
+        }
+    }
+}
+
+/// The rows of a table with the code statistics of each language in
+/// `totals`, with the numbers right aligned under a header row.
+fn code_stats_table(totals: &LanguageTotals) -> Vec<String> {
+    let header = ["Language", "Files", "Code", "Comments", "Blanks"];
+    let mut rows = vec![header.map(String::from)];
+    for (name, total) in totals.iter() {
+        let stats = &total.stats;
+        rows.push([
+            name.to_string(),
+            total.files.to_string(),
+            stats.code.to_string(),
+            stats.comments.to_string(),
+            stats.blanks.to_string(),
+        ]);
+    }
+
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| rows.iter().map(|row| row[i].len()).max().unwrap_or(0))
+        .collect();
+    rows.iter()
+        .map(|row| {
+            let mut line = format!("{:<width$}", row[0], width = widths[0]);
+            for (field, width) in row.iter().zip(&widths).skip(1) {
+                line.push_str(&format!(" {:>width$}", field));
+            }
+            line
+        })
+        .collect()
+}
+
+/// The code statistics of each language in `totals` as JSON objects
+/// separated by commas.
+fn code_stats_json(totals: &LanguageTotals) -> String {
+    totals
+        .iter()
+        .map(|(name, total)| {
+            format!(
+                "{{\"language\":{},\"files\":{},\"code\":{},\"comments\":{},\"blanks\":{}}}",
+                json_string(name),
+                total.files,
+                total.stats.code,
+                total.stats.comments,
+                total.stats.blanks
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// The rows of a table with the counts of the most frequent `words`, right
+/// aligned under a header row.
+fn word_freq_table(words: &[(String, usize)]) -> Vec<String> {
+    let header = "Count";
+    let width = words
+        .iter()
+        .map(|(_, count)| count.to_string().len())
+        .chain([header.len()])
+        .max()
+        .unwrap_or(0);
+    let mut rows = vec![format!("{:>width$} Word", header)];
+    rows.extend(
+        words
+            .iter()
+            .map(|(word, count)| format!("{:>width$} {}", count, word)),
+    );
+    rows
+}
+
+/// The most frequent `words` as JSON objects separated by commas.
+fn word_freq_json(words: &[(String, usize)]) -> String {
+    words
+        .iter()
+        .map(|(word, count)| format!("{{\"word\":{},\"count\":{}}}", json_string(word), count))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Quote `s` for a CSV field if it contains a separator, quote or line
+/// break, doubling any quotes, as in RFC 4180.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Escape tabs, line breaks and backslashes in `s` for a TSV field, which
+/// has no quoting.
+fn tsv_field(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\\' => escaped.push_str("\\\\"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Quote `s` as a JSON string.
+fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::code_stats::{CodeStats, LANGUAGES};
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("a.txt"), "\"a.txt\"");
+        assert_eq!(
+            json_string("we\"ird\\name\n\t\u{1}"),
+            "\"we\\\"ird\\\\name\\n\\t\\u0001\""
+        );
+        assert_eq!(json_string("日本"), "\"日本\"");
+    }
+
+    #[test]
+    fn test_text() {
+        let options = DisplayOptions::default_options();
+        let mut printer = Printer::new(OutputFormat::Text, &options, 2);
+        assert_eq!(printer.header(), None);
+        let result = FileResult::new(1, 2, 3, 4);
+        assert_eq!(printer.result(Some("a"), &result).unwrap(), " 1  2  4 a");
+        assert_eq!(printer.result(None, &result).unwrap(), " 1  2  4");
+        let error = io::Error::from(io::ErrorKind::NotFound);
+        assert_eq!(printer.error("b", &error), None);
+        assert_eq!(
+            printer.footer(Some(&result), true).unwrap(),
+            " 1  2  4 total"
+        );
+        assert_eq!(printer.footer(Some(&result), false).unwrap(), " 1  2  4");
+        assert_eq!(printer.footer(None, true), None);
+    }
+
+    #[test]
+    fn test_json() {
+        let options = DisplayOptions::new(true, true, false, false)
+            .join(&DisplayOptions::with_max_line_length_only());
+        let mut printer = Printer::new(OutputFormat::Json, &options, 7);
+        let mut lines = Vec::new();
+        lines.extend(printer.header());
+        lines.extend(printer.result(Some("a"), &FileResult::new(1, 2, 3, 4)));
+        let error = io::Error::new(io::ErrorKind::NotFound, "not found");
+        lines.extend(printer.error("b", &error));
+        lines.extend(printer.result(None, &FileResult::new(5, 6, 7, 8)));
+        lines.extend(printer.footer(Some(&FileResult::new(6, 8, 10, 12)), true));
+        assert_eq!(
+            lines.join("\n"),
+            concat!(
+                "{\"inputs\":[\n",
+                "{\"path\":\"a\",\"lines\":1,\"words\":2,\"max_line_length\":0},\n",
+                "{\"path\":\"b\",\"error\":\"not found\"},\n",
+                "{\"path\":\"-\",\"lines\":5,\"words\":6,\"max_line_length\":0}\n",
+                "],\"total\":{\"lines\":6,\"words\":8,\"max_line_length\":0}}"
+            )
+        );
+
+        // no inputs and no total
+        let mut printer = Printer::new(OutputFormat::Json, &options, 7);
+        assert_eq!(printer.footer(None, false).unwrap(), "]}");
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("a.txt"), "a.txt");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("tab\there"), "tab\there");
+    }
+
+    #[test]
+    fn test_tsv_field() {
+        assert_eq!(tsv_field("a.txt"), "a.txt");
+        assert_eq!(tsv_field("tab\there"), "tab\\there");
+        assert_eq!(tsv_field("two\r\nlines"), "two\\r\\nlines");
+        assert_eq!(tsv_field("back\\slash,\"q\""), "back\\\\slash,\"q\"");
+    }
+
+    #[test]
+    fn test_csv() {
+        let options = DisplayOptions::new(true, false, true, false);
+        let mut printer = Printer::new(OutputFormat::Csv, &options, 7);
+        let mut lines = Vec::new();
+        lines.extend(printer.header());
+        lines.extend(printer.result(Some("a,b"), &FileResult::new(1, 2, 3, 4)));
+        let error = io::Error::from(io::ErrorKind::NotFound);
+        lines.extend(printer.error("c", &error));
+        lines.extend(printer.result(None, &FileResult::new(5, 6, 7, 8)));
+        lines.extend(printer.footer(Some(&FileResult::new(6, 8, 10, 12)), true));
+        assert_eq!(
+            lines,
+            [
+                "type,path,lines,chars",
+                "input,\"a,b\",1,3",
+                "input,-,5,7",
+                "total,,6,10"
+            ]
+        );
+        assert_eq!(printer.footer(None, false), None);
+    }
+
+    #[test]
+    fn test_tsv() {
+        let options = DisplayOptions::with_bytes_only();
+        let mut printer = Printer::new(OutputFormat::Tsv, &options, 7);
+        let mut lines = Vec::new();
+        lines.extend(printer.header());
+        lines.extend(printer.result(Some("a\tb"), &FileResult::new(1, 2, 3, 4)));
+        lines.extend(printer.footer(Some(&FileResult::new(1, 2, 3, 4)), false));
+        assert_eq!(
+            lines,
+            ["type\tpath\tbytes", "input\ta\\tb\t4", "total\t\t4"]
+        );
+    }
+
+    #[test]
+    fn test_code_stats() {
+        let mut totals = LanguageTotals::default();
+        for language in LANGUAGES.iter().filter(|l| ["Rust", "C"].contains(&l.name)) {
+            totals.add(language, &CodeStats::new(1234, 56, 7));
+        }
+        let options = DisplayOptions::with_lines_only();
+
+        let mut printer = Printer::new(OutputFormat::Text, &options, 2);
+        printer.set_code_stats(totals.clone());
+        assert_eq!(
+            printer
+                .footer(Some(&FileResult::new(1, 2, 3, 4)), true)
+                .unwrap(),
+            concat!(
+                " 1 total\n",
+                "Language Files Code Comments Blanks\n",
+                "C            1 1234       56      7\n",
+                "Rust         1 1234       56      7"
+            )
+        );
+
+        let mut printer = Printer::new(OutputFormat::Json, &options, 2);
+        printer.set_code_stats(totals);
+        assert_eq!(
+            printer.footer(None, false).unwrap(),
+            concat!(
+                "],\"languages\":[",
+                "{\"language\":\"C\",\"files\":1,\"code\":1234,\"comments\":56,\"blanks\":7},",
+                "{\"language\":\"Rust\",\"files\":1,\"code\":1234,\"comments\":56,\"blanks\":7}",
+                "]}"
+            )
+        );
+    }
+
+    #[test]
+    fn test_word_freq() {
+        let words = vec![(String::from("the"), 123456), (String::from("\"a\""), 7)];
+        let options = DisplayOptions::with_words_only();
+
+        let mut printer = Printer::new(OutputFormat::Text, &options, 1);
+        printer.set_word_freq(words.clone());
+        assert_eq!(
+            printer.footer(None, false).unwrap(),
+            " Count Word\n123456 the\n     7 \"a\""
+        );
+
+        let mut printer = Printer::new(OutputFormat::Json, &options, 1);
+        printer.set_word_freq(words);
+        assert_eq!(
+            printer.footer(None, false).unwrap(),
+            "],\"word_freq\":[{\"word\":\"the\",\"count\":123456},{\"word\":\"\\\"a\\\"\",\"count\":7}]}"
+        );
+    }
+}
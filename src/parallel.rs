@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+use crate::counter::{for_each_chunk, CHUNK_SIZE};
+use crate::display_options::DisplayOptions;
+use crate::file_result::FileResult;
+use crate::partial::PartialResult;
+
+/// Call `work` on every element of `items` using a pool of `jobs` worker
+/// threads, and pass each element with its result to `emit` in the same
+/// order as `items`. Results that finish early are held back until every
+/// result before them has been emitted.
+pub fn for_each_ordered<T, R, W, E>(items: &[T], jobs: usize, work: W, mut emit: E)
+where
+    T: Sync,
+    R: Send,
+    W: Fn(&T) -> R + Sync,
+    E: FnMut(&T, R),
+{
+    let jobs = jobs.min(items.len());
+    // no point in spawning threads for a single worker
+    if jobs <= 1 {
+        items.iter().for_each(|item| emit(item, work(item)));
+        return;
+    }
+
+    let next_item = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            let sender = sender.clone();
+            let next_item = &next_item;
+            let work = &work;
+            scope.spawn(move || loop {
+                let i = next_item.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(i) else {
+                    break;
+                };
+                // the receiver only hangs up if the emitting thread panicked
+                if sender.send((i, work(item))).is_err() {
+                    break;
+                }
+            });
+        }
+        // drop our sender so the receiver ends once all workers are done
+        drop(sender);
+
+        let mut finished = BTreeMap::new();
+        let mut next_to_emit = 0;
+        for (i, result) in receiver {
+            finished.insert(i, result);
+            while let Some(result) = finished.remove(&next_to_emit) {
+                emit(&items[next_to_emit], result);
+                next_to_emit += 1;
+            }
+        }
+    });
+}
+
+/// Compute the partial result for the `len` bytes of the file at `path`
+/// starting at byte `start`, estimating the distinct words with a sketch
+/// of `precision` if it is given.
+fn partial_for_range(
+    path: &Path,
+    start: u64,
+    len: u64,
+    precision: Option<u8>,
+) -> io::Result<PartialResult> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut partial = match precision {
+        Some(precision) => PartialResult::with_distinct_words(&[], precision),
+        None => PartialResult::default(),
+    };
+    for_each_chunk(&mut file.take(len), |chunk| partial.update(chunk))?;
+    Ok(partial)
+}
+
+/// Compute line, word, character, and byte counts for the regular file at
+/// `path` by splitting it into up to `jobs` byte ranges which are counted
+/// on separate threads and merged in order. The maximum line length is not
+/// computed, see `PartialResult`, and the distinct words are only estimated
+/// if they are displayed in `options`.
+pub fn counts_for_path_split(
+    path: &Path,
+    jobs: usize,
+    options: &DisplayOptions,
+) -> io::Result<FileResult> {
+    let precision = options.distinct_words();
+    let len = std::fs::metadata(path)?.len();
+    // don't bother splitting off ranges smaller than a single chunk
+    let ranges = (jobs as u64).min(len.div_ceil(CHUNK_SIZE as u64)).max(1);
+    let range_len = len.div_ceil(ranges);
+    let partials: Vec<io::Result<PartialResult>> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..ranges)
+            .map(|i| {
+                let start = i * range_len;
+                let end = (start + range_len).min(len);
+                scope.spawn(move || partial_for_range(path, start, end - start, precision))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("counting thread panicked"))
+            .collect()
+    });
+
+    let mut result = PartialResult::default();
+    for partial in partials {
+        result = result.merge(&partial?);
+    }
+    Ok(result.finish())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::count_options::CountOptions;
+    use crate::counter::Counter;
+    use crate::file_result::counts_for_file;
+
+    #[test]
+    fn test_for_each_ordered() {
+        let items: Vec<usize> = (0..100).collect();
+        for jobs in [1, 2, 7, 200] {
+            let mut emitted = Vec::new();
+            for_each_ordered(
+                &items,
+                jobs,
+                |&i| {
+                    // make early items finish last
+                    thread::sleep(std::time::Duration::from_micros((100 - i as u64) * 10));
+                    i * 2
+                },
+                |&i, result| emitted.push((i, result)),
+            );
+            let expected: Vec<(usize, usize)> = items.iter().map(|&i| (i, i * 2)).collect();
+            assert_eq!(emitted, expected);
+        }
+    }
+
+    #[test]
+    fn test_for_each_ordered_empty() {
+        let items: Vec<usize> = Vec::new();
+        let mut emitted = 0;
+        for_each_ordered(&items, 4, |&i| i, |_, _| emitted += 1);
+        assert_eq!(emitted, 0);
+    }
+
+    #[test]
+    fn test_counts_for_path_split() {
+        let contents = "wörd wörd\n日本語 テキスト\t".repeat(20_000);
+        let path = std::env::temp_dir().join(format!("wcrs-split-{}", std::process::id()));
+        std::fs::write(&path, &contents).unwrap();
+        let expected = FileResult {
+            max_line_length: 0,
+            columns: 0,
+            ..counts_for_file(contents.as_bytes())
+        };
+        for jobs in [1, 2, 3, 8] {
+            let result = counts_for_path_split(&path, jobs, &DisplayOptions::default());
+            assert_eq!(result.unwrap(), expected);
+        }
+        // words crossing the ranges are only inserted into the sketch once
+        let options = DisplayOptions::with_distinct_words_only(8);
+        let mut counter = Counter::for_options(&options, &CountOptions::default());
+        counter.update(contents.as_bytes());
+        let expected = counter.finish();
+        for jobs in [1, 2, 3, 8] {
+            assert_eq!(
+                counts_for_path_split(&path, jobs, &options).unwrap(),
+                expected
+            );
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,364 @@
+use crate::counter::is_incomplete_char;
+use crate::distinct::{PartialSketch, Sketch};
+use crate::file_result::{counts_for_file, FileResult};
+
+/// Counts for a run of bytes whose UTF-8 decoding does not depend on the
+/// bytes around it, along with what is needed to join it to the runs next
+/// to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Span {
+    /// `lines` is the number of newlines, a final line without a newline
+    /// is only counted by `PartialResult::finish`, `max_line_length` and
+    /// `columns` are always 0, `blank_lines` and `non_blank_lines` only
+    /// count the lines between the first and last newline, and
+    /// `distinct_words` is only set by `PartialResult::finish`
+    counts: FileResult,
+    /// whether the first character is part of a word
+    starts_in_word: bool,
+    /// whether the last character is part of a word
+    ends_in_word: bool,
+    /// whether the last byte is a newline
+    ends_with_newline: bool,
+    /// whether there is a newline anywhere
+    has_newline: bool,
+    /// whether everything before the first newline is whitespace
+    first_blank: bool,
+    /// whether everything after the last newline is whitespace
+    last_blank: bool,
+    /// the distinct words, if they are estimated
+    distinct: Option<PartialSketch>,
+}
+
+impl Span {
+    /// Compute the `Span` for `bytes`, which must not be empty, estimating
+    /// the distinct words with a sketch of `precision` if it is given.
+    /// An incomplete character at the end of `bytes` is counted as invalid.
+    fn of(bytes: &[u8], precision: Option<u8>) -> Self {
+        let mut counts = counts_for_file(bytes);
+        counts.lines = bytes.iter().filter(|&&b| b == b'\n').count();
+        counts.max_line_length = 0;
+        counts.columns = 0;
+        let pieces: Vec<&[u8]> = bytes.split(|&b| b == b'\n').collect();
+        let inner = pieces.get(1..pieces.len() - 1).unwrap_or_default();
+        counts.blank_lines = inner.iter().filter(|line| is_blank(line)).count();
+        counts.non_blank_lines = inner.len() - counts.blank_lines;
+        let first = bytes.utf8_chunks().next();
+        let last = bytes.utf8_chunks().last();
+        Self {
+            counts,
+            starts_in_word: first.is_some_and(|c| match c.valid().chars().next() {
+                Some(c) => !c.is_whitespace(),
+                // invalid bytes are part of a word
+                None => true,
+            }),
+            ends_in_word: last.is_some_and(|c| {
+                !c.invalid().is_empty()
+                    || c.valid().chars().last().is_some_and(|c| !c.is_whitespace())
+            }),
+            ends_with_newline: bytes.last() == Some(&b'\n'),
+            has_newline: pieces.len() > 1,
+            first_blank: is_blank(pieces[0]),
+            last_blank: is_blank(pieces[pieces.len() - 1]),
+            distinct: precision.map(|precision| PartialSketch::of(bytes, precision)),
+        }
+    }
+
+    /// Join `self` with the span that directly follows it.
+    fn join(&self, other: &Self) -> Self {
+        let mut counts = self.counts.add(&other.counts);
+        // a word crossing the boundary was counted on both sides
+        if self.ends_in_word && other.starts_in_word {
+            counts.words -= 1;
+        }
+        // the line between the last newline of `self` and the first newline
+        // of `other` is only complete once both are joined
+        if self.has_newline && other.has_newline {
+            count_line_kind(&mut counts, self.last_blank && other.first_blank);
+        }
+        Self {
+            counts,
+            starts_in_word: self.starts_in_word,
+            ends_in_word: other.ends_in_word,
+            ends_with_newline: other.ends_with_newline,
+            has_newline: self.has_newline || other.has_newline,
+            first_blank: if self.has_newline {
+                self.first_blank
+            } else {
+                self.first_blank && other.first_blank
+            },
+            last_blank: if other.has_newline {
+                other.last_blank
+            } else {
+                self.last_blank && other.first_blank
+            },
+            distinct: match (&self.distinct, &other.distinct) {
+                (Some(a), Some(b)) => Some(a.join(b)),
+                (a, b) => a.clone().or_else(|| b.clone()),
+            },
+        }
+    }
+}
+
+/// Returns `true` if `bytes` is valid UTF-8 consisting only of whitespace.
+fn is_blank(bytes: &[u8]) -> bool {
+    bytes
+        .utf8_chunks()
+        .all(|chunk| chunk.invalid().is_empty() && chunk.valid().chars().all(char::is_whitespace))
+}
+
+/// Count a line in `counts` as blank if `blank` and non-blank otherwise.
+fn count_line_kind(counts: &mut FileResult, blank: bool) {
+    if blank {
+        counts.blank_lines += 1;
+    } else {
+        counts.non_blank_lines += 1;
+    }
+}
+
+/// Join optional spans, skipping the missing ones.
+fn join_spans<'a, I: IntoIterator<Item = Option<&'a Span>>>(spans: I) -> Option<Span> {
+    spans
+        .into_iter()
+        .flatten()
+        .fold(None, |acc: Option<Span>, span| match acc {
+            Some(acc) => Some(acc.join(span)),
+            None => Some(span.clone()),
+        })
+}
+
+/// Returns `true` if `b` is a UTF-8 continuation byte.
+fn is_continuation(b: u8) -> bool {
+    b & 0b1100_0000 == 0b1000_0000
+}
+
+/// The span of `bytes` if it is not empty, see `Span::of`.
+fn span_of(bytes: &[u8], precision: Option<u8>) -> Option<Span> {
+    (!bytes.is_empty()).then(|| Span::of(bytes, precision))
+}
+
+/// Counts for an arbitrary byte range of an input, which can be split
+/// anywhere, including inside a word or a multi-byte character.
+///
+/// Partial results for neighbouring ranges are combined with `merge`, which
+/// is associative and has `PartialResult::default()` as its identity, so an
+/// input can be split into ranges that are counted independently and merged
+/// in order to get the same counts as counting the whole input at once.
+///
+/// Only lines, words, characters, and bytes are counted, the maximum line
+/// length and the total display width are always 0. The distinct words are
+/// only estimated for partial results created with `with_distinct_words`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PartialResult {
+    /// the precision of the sketch estimating the distinct words, if they
+    /// are estimated
+    precision: Option<u8>,
+    /// leading continuation bytes which may finish a character that
+    /// started before this range
+    head: Vec<u8>,
+    /// counts for the bytes between `head` and `tail`
+    body: Option<Span>,
+    /// the start of a character at the end of this range which may be
+    /// finished after it
+    tail: Vec<u8>,
+}
+
+impl PartialResult {
+    /// Compute the partial result for the byte range `bytes`.
+    pub fn of(bytes: &[u8]) -> Self {
+        Self::of_with_precision(bytes, None)
+    }
+
+    /// Compute the partial result for the byte range `bytes`, also
+    /// estimating the distinct words with a sketch of `precision`.
+    pub fn with_distinct_words(bytes: &[u8], precision: u8) -> Self {
+        Self::of_with_precision(bytes, Some(precision))
+    }
+
+    fn of_with_precision(bytes: &[u8], precision: Option<u8>) -> Self {
+        // a character has at most 3 continuation bytes, any more than
+        // that at the start of the range can not finish a character
+        let head_len = bytes
+            .iter()
+            .take(3)
+            .take_while(|&&b| is_continuation(b))
+            .count();
+        let (head, rest) = bytes.split_at(head_len);
+        let tail_len = match rest.utf8_chunks().last() {
+            Some(chunk) if is_incomplete_char(chunk.invalid()) => chunk.invalid().len(),
+            _ => 0,
+        };
+        let (body, tail) = rest.split_at(rest.len() - tail_len);
+        Self {
+            precision,
+            head: head.to_vec(),
+            body: span_of(body, precision),
+            tail: tail.to_vec(),
+        }
+    }
+
+    /// Returns `true` if the range consists only of leading continuation
+    /// bytes.
+    fn only_head(&self) -> bool {
+        self.body.is_none() && self.tail.is_empty()
+    }
+
+    /// Merge `self` with the partial result of the range that directly
+    /// follows it, returning the partial result of both ranges together.
+    pub fn merge(&self, other: &Self) -> Self {
+        let precision = self.precision.or(other.precision);
+        if self.only_head() {
+            // the leading continuation bytes of both ranges form one run,
+            // only the first 3 of which may still finish a character
+            let run = [self.head.as_slice(), other.head.as_slice()].concat();
+            let (head, excess) = run.split_at(run.len().min(3));
+            return Self {
+                precision,
+                head: head.to_vec(),
+                body: join_spans([span_of(excess, precision).as_ref(), other.body.as_ref()]),
+                tail: other.tail.clone(),
+            };
+        }
+
+        // the character at the end of `self` and the continuation bytes at
+        // the start of `other` can now be decoded together
+        let junction = [self.tail.as_slice(), other.head.as_slice()].concat();
+        if other.only_head() {
+            // nothing after the junction yet, so it may still be incomplete
+            if is_incomplete_char(&junction) {
+                return Self {
+                    precision,
+                    head: self.head.clone(),
+                    body: self.body.clone(),
+                    tail: junction,
+                };
+            }
+            return Self {
+                precision,
+                head: self.head.clone(),
+                body: join_spans([self.body.as_ref(), span_of(&junction, precision).as_ref()]),
+                tail: Vec::new(),
+            };
+        }
+        Self {
+            precision,
+            head: self.head.clone(),
+            body: join_spans([
+                self.body.as_ref(),
+                span_of(&junction, precision).as_ref(),
+                other.body.as_ref(),
+            ]),
+            tail: other.tail.clone(),
+        }
+    }
+
+    /// Merge the partial result of `bytes`, which directly follow the
+    /// range of `self`, into `self`.
+    pub fn update(&mut self, bytes: &[u8]) {
+        *self = self.merge(&Self::of_with_precision(bytes, self.precision));
+    }
+
+    /// Treat `self` as the partial result of a whole input and return its
+    /// counts. Bytes that never formed a character are counted as invalid.
+    pub fn finish(&self) -> FileResult {
+        let span = join_spans([
+            span_of(&self.head, self.precision).as_ref(),
+            self.body.as_ref(),
+            span_of(&self.tail, self.precision).as_ref(),
+        ]);
+        match span {
+            Some(span) => {
+                let mut result = span.counts;
+                // the first line starts at the start of the input
+                if span.has_newline {
+                    count_line_kind(&mut result, span.first_blank);
+                }
+                // a final line without a trailing newline still counts as a line
+                if !span.ends_with_newline {
+                    result.lines += 1;
+                    count_line_kind(&mut result, span.last_blank);
+                }
+                result.distinct_words = span.distinct.as_ref().map(PartialSketch::finish);
+                result
+            }
+            None => FileResult {
+                distinct_words: self.precision.map(Sketch::new),
+                ..FileResult::default()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::count_options::CountOptions;
+    use crate::counter::Counter;
+    use crate::display_options::DisplayOptions;
+
+    /// The counts `PartialResult` computes for `bytes` when counted at once.
+    fn sequential(bytes: &[u8]) -> FileResult {
+        FileResult {
+            max_line_length: 0,
+            columns: 0,
+            ..counts_for_file(bytes)
+        }
+    }
+
+    #[test]
+    fn test_merge_identity() {
+        let partial = PartialResult::of("a b\nc€".as_bytes());
+        assert_eq!(partial.merge(&PartialResult::default()), partial);
+        assert_eq!(PartialResult::default().merge(&partial), partial);
+        assert_eq!(PartialResult::default().finish(), FileResult::default());
+    }
+
+    #[test]
+    fn test_merge_splits() {
+        let s = "héllo wörld\n日本語  テキスト\n\tlast \u{3000}line 🦀\u{a0}x";
+        let bytes = s.as_bytes();
+        for i in 0..=bytes.len() {
+            for j in i..=bytes.len() {
+                let merged = PartialResult::of(&bytes[..i])
+                    .merge(&PartialResult::of(&bytes[i..j]))
+                    .merge(&PartialResult::of(&bytes[j..]));
+                assert_eq!(merged, PartialResult::of(bytes), "split at {i}, {j}");
+                assert_eq!(merged.finish(), sequential(bytes));
+            }
+        }
+    }
+
+    #[test]
+    fn test_merge_distinct_words() {
+        let s = "héllo wörld\u{3000}wörld\n héllo\u{a0}\u{ff} \u{3000}";
+        let bytes = s.as_bytes();
+        let options = DisplayOptions::with_distinct_words_only(6);
+        let mut counter = Counter::for_options(&options, &CountOptions::default());
+        counter.update(bytes);
+        let expected = counter.finish();
+        for i in 0..=bytes.len() {
+            let merged = PartialResult::with_distinct_words(&bytes[..i], 6)
+                .merge(&PartialResult::with_distinct_words(&bytes[i..], 6));
+            assert_eq!(
+                merged.finish().distinct_words,
+                expected.distinct_words,
+                "split at {i}"
+            );
+        }
+        assert_eq!(
+            PartialResult::with_distinct_words(b"", 6)
+                .finish()
+                .distinct_words,
+            Some(Sketch::new(6))
+        );
+    }
+
+    #[test]
+    fn test_merge_invalid() {
+        let bytes = b"\x80\x80\x80\x80 \xf0\x9f\xa6 \xe2\x82\xac\x80\n\xf0";
+        for i in 0..=bytes.len() {
+            let merged = PartialResult::of(&bytes[..i]).merge(&PartialResult::of(&bytes[i..]));
+            assert_eq!(merged, PartialResult::of(bytes), "split at {i}");
+            assert_eq!(merged.finish(), sequential(bytes));
+        }
+    }
+}
@@ -0,0 +1,546 @@
+use std::ffi::OsStr;
+use std::fs::{self, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::glob::PathFilter;
+use crate::ignore::Ignore;
+
+/// How directories are walked by `walk`.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct WalkOptions {
+    /// follow symbolic links to files and directories instead of skipping them
+    pub follow_symlinks: bool,
+    /// the deepest level to look for files at, where the entries of the
+    /// directory being walked are at depth 1
+    pub max_depth: Option<usize>,
+    /// include files and directories whose names start with `.`, other than
+    /// `.git` which is only walked with `no_ignore`
+    pub hidden: bool,
+    /// don't descend into directories on other file systems
+    pub one_file_system: bool,
+    /// count files even if they are ignored by `.gitignore`, `.ignore` or
+    /// `.git/info/exclude` files, see `Ignore`, and walk `.git` directories
+    pub no_ignore: bool,
+}
+
+/// The regular files found by `walk`, and the paths that could not be
+/// walked along with why.
+#[derive(Debug, Default)]
+pub struct Walk {
+    pub files: Vec<PathBuf>,
+    pub errors: Vec<(PathBuf, io::Error)>,
+}
+
+/// Walk the directory `root`, adding every regular file in it and its
+/// subdirectories to `walk.files` in sorted order, so the order does not
+/// depend on the file system. Anything that can't be read is added to
+/// `walk.errors` instead, and the rest of the walk carries on. Files and
+/// directories are skipped if `filter` does not allow their path relative
+/// to `root`, or unless `options.no_ignore` is set, if they are ignored by
+/// the ignore files of the directories they are in or named `.git`. Ignore
+/// files above `root` are only read up to the root of the git repository
+/// `root` is in, along with its `.git/info/exclude`, see `outer_ignores`.
+///
+/// `root` itself is always walked, even if it is hidden or a symbolic link.
+/// When following symbolic links, a link back to a directory that is
+/// already being walked is reported as an error instead of being followed
+/// forever.
+pub fn walk(root: &Path, options: &WalkOptions, filter: &PathFilter, walk: &mut Walk) {
+    let device = match fs::metadata(root) {
+        Ok(metadata) => device(&metadata),
+        Err(e) => {
+            walk.errors.push((root.to_path_buf(), e));
+            return;
+        }
+    };
+    let outer_ignores = if options.no_ignore {
+        Vec::new()
+    } else {
+        outer_ignores(root).unwrap_or_else(|e| {
+            walk.errors.push((root.to_path_buf(), e));
+            Vec::new()
+        })
+    };
+    let mut walker = Walker {
+        root,
+        options,
+        filter,
+        device,
+        ancestors: Vec::new(),
+        outer_ignores,
+        ignores: Vec::new(),
+        walk,
+    };
+    walker.walk_dir(root, 1);
+}
+
+/// The state of a single call to `walk`.
+struct Walker<'a> {
+    root: &'a Path,
+    options: &'a WalkOptions,
+    filter: &'a PathFilter,
+    /// the device of the root directory
+    device: Option<u64>,
+    /// the canonical paths of the directories being walked, used to detect
+    /// loops when following symbolic links
+    ancestors: Vec<PathBuf>,
+    /// the rules of the ignore files that apply to the root directory from
+    /// outside of it, see `outer_ignores`
+    outer_ignores: Vec<(PathBuf, Ignore)>,
+    /// the rules of the ignore files in the directories being walked, along
+    /// with the directory they are in, from shallowest to deepest
+    ignores: Vec<(PathBuf, Ignore)>,
+    walk: &'a mut Walk,
+}
+
+impl Walker<'_> {
+    /// Walk the entries of `dir`, which are at `depth`.
+    fn walk_dir(&mut self, dir: &Path, depth: usize) {
+        if self.options.max_depth.is_some_and(|max| depth > max) {
+            return;
+        }
+        if self.options.follow_symlinks {
+            match fs::canonicalize(dir) {
+                Ok(real) if self.ancestors.contains(&real) => {
+                    let error = io::Error::other("recursive directory loop");
+                    self.walk.errors.push((dir.to_path_buf(), error));
+                    return;
+                }
+                Ok(real) => self.ancestors.push(real),
+                Err(e) => {
+                    self.walk.errors.push((dir.to_path_buf(), e));
+                    return;
+                }
+            }
+        }
+
+        let ignore = if self.options.no_ignore {
+            Ignore::default()
+        } else {
+            Ignore::for_dir(dir).unwrap_or_else(|e| {
+                self.walk.errors.push((dir.to_path_buf(), e));
+                Ignore::default()
+            })
+        };
+        let has_ignore = !ignore.is_empty();
+        if has_ignore {
+            self.ignores.push((dir.to_path_buf(), ignore));
+        }
+
+        match sorted_entries(dir) {
+            Ok(paths) => {
+                for path in paths {
+                    self.visit(path, depth);
+                }
+            }
+            Err(e) => self.walk.errors.push((dir.to_path_buf(), e)),
+        }
+
+        if has_ignore {
+            self.ignores.pop();
+        }
+        if self.options.follow_symlinks {
+            self.ancestors.pop();
+        }
+    }
+
+    /// Add `path` to the files found if it is a regular file, or walk it if
+    /// it is a directory.
+    fn visit(&mut self, path: PathBuf, depth: usize) {
+        if !self.options.hidden && is_hidden(&path) {
+            return;
+        }
+        // the files of a git repository itself are never counted, like git
+        // and other tools that respect ignore files skip them
+        if !self.options.no_ignore && path.file_name() == Some(OsStr::new(".git")) {
+            return;
+        }
+        // without following them, symbolic links are neither files nor
+        // directories so they are skipped
+        let metadata = if self.options.follow_symlinks {
+            fs::metadata(&path)
+        } else {
+            fs::symlink_metadata(&path)
+        };
+        let metadata = match metadata {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                self.walk.errors.push((path, e));
+                return;
+            }
+        };
+
+        if self.is_ignored(&path, metadata.is_dir()) {
+            return;
+        }
+
+        let relative = path.strip_prefix(self.root).unwrap_or(&path);
+        if metadata.is_file() {
+            if self.filter.allows_file(relative) {
+                self.walk.files.push(path);
+            }
+        } else if metadata.is_dir()
+            && self.filter.allows_dir(relative)
+            && (!self.options.one_file_system || device(&metadata) == self.device)
+        {
+            self.walk_dir(&path, depth + 1);
+        }
+    }
+
+    /// Returns `true` if `path` is ignored by the rules of the deepest
+    /// ignore file with a rule matching it.
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.ignores
+            .iter()
+            .rev()
+            .find_map(|(dir, ignore)| {
+                let relative = path.strip_prefix(dir).ok()?;
+                ignore.matched(relative, is_dir)
+            })
+            .or_else(|| {
+                let relative = path.strip_prefix(self.root).ok()?;
+                self.outer_ignores
+                    .iter()
+                    .rev()
+                    .find_map(|(root, ignore)| ignore.matched(&root.join(relative), is_dir))
+            })
+            .unwrap_or(false)
+    }
+}
+
+/// The rules of the ignore files that apply to `root` from outside of it,
+/// from lowest to highest precedence, each along with the path of `root`
+/// relative to the directory of the rules. These are the `.git/info/exclude`
+/// of the git repository `root` is in, found by looking for `.git` in `root`
+/// and its ancestors, and the ignore files of the directories from the root
+/// of the repository down to the parent of `root`. Nothing is read if `root`
+/// is not in a git repository.
+fn outer_ignores(root: &Path) -> io::Result<Vec<(PathBuf, Ignore)>> {
+    let root = fs::canonicalize(root)?;
+    let Some(repository) = root.ancestors().find(|dir| dir.join(".git").exists()) else {
+        return Ok(Vec::new());
+    };
+    let relative_to = |dir: &Path| root.strip_prefix(dir).unwrap_or(&root).to_path_buf();
+    let mut ignores = vec![(relative_to(repository), Ignore::for_repository(repository))];
+    let mut dirs: Vec<&Path> = root
+        .ancestors()
+        .skip(1)
+        .take_while(|dir| dir.starts_with(repository))
+        .collect();
+    dirs.reverse();
+    for dir in dirs {
+        ignores.push((relative_to(dir), Ignore::for_dir(dir)?));
+    }
+    Ok(ignores)
+}
+
+/// The paths of the entries of `dir` sorted by name.
+fn sorted_entries(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    Ok(entries.into_iter().map(|entry| entry.path()).collect())
+}
+
+/// Returns `true` if the name of `path` starts with `.`.
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.as_encoded_bytes().starts_with(b"."))
+}
+
+/// The device the file with `metadata` is on, if the platform has them.
+#[cfg(unix)]
+fn device(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Create a directory tree for testing under the temporary directory,
+    /// named after `name` and the process id.
+    fn test_tree(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("wcrs-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for dir in ["b/d", ".hidden", "a"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            "z.txt",
+            "b/c.txt",
+            "b/d/e.txt",
+            ".hidden/f.txt",
+            ".g.txt",
+            "a/h.txt",
+        ] {
+            fs::write(root.join(file), file).unwrap();
+        }
+        root
+    }
+
+    /// Walk `root` with `options` and `filter` returning the files found
+    /// relative to `root`.
+    fn walk_filtered(root: &Path, options: &WalkOptions, filter: &PathFilter) -> Vec<String> {
+        let mut found = Walk::default();
+        walk(root, options, filter, &mut found);
+        assert!(found.errors.is_empty(), "{:?}", found.errors);
+        found
+            .files
+            .iter()
+            .map(|path| {
+                path.strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect()
+    }
+
+    fn walk_relative(root: &Path, options: &WalkOptions) -> Vec<String> {
+        walk_filtered(root, options, &PathFilter::default())
+    }
+
+    #[test]
+    fn test_walk() {
+        let root = test_tree("walk");
+        let options = WalkOptions::default();
+        assert_eq!(
+            walk_relative(&root, &options),
+            ["a/h.txt", "b/c.txt", "b/d/e.txt", "z.txt"]
+        );
+
+        let options = WalkOptions {
+            hidden: true,
+            ..WalkOptions::default()
+        };
+        assert_eq!(
+            walk_relative(&root, &options),
+            [
+                ".g.txt",
+                ".hidden/f.txt",
+                "a/h.txt",
+                "b/c.txt",
+                "b/d/e.txt",
+                "z.txt"
+            ]
+        );
+
+        let options = WalkOptions {
+            max_depth: Some(2),
+            ..WalkOptions::default()
+        };
+        assert_eq!(
+            walk_relative(&root, &options),
+            ["a/h.txt", "b/c.txt", "z.txt"]
+        );
+        let options = WalkOptions {
+            max_depth: Some(0),
+            ..WalkOptions::default()
+        };
+        assert!(walk_relative(&root, &options).is_empty());
+
+        let mut found = Walk::default();
+        let filter = PathFilter::default();
+        walk(
+            &root.join("missing"),
+            &WalkOptions::default(),
+            &filter,
+            &mut found,
+        );
+        assert!(found.files.is_empty());
+        assert_eq!(found.errors.len(), 1);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_walk_filtered() {
+        let root = test_tree("walk-filtered");
+        let options = WalkOptions::default();
+        let mut filter = PathFilter::default();
+        filter.include("*.txt");
+        filter.exclude("b/d/**");
+        filter.exclude("z.*");
+        assert_eq!(
+            walk_filtered(&root, &options, &filter),
+            ["a/h.txt", "b/c.txt"]
+        );
+
+        let mut filter = PathFilter::default();
+        filter.include("b/**");
+        assert_eq!(
+            walk_filtered(&root, &options, &filter),
+            ["b/c.txt", "b/d/e.txt"]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_walk_ignore() {
+        let root = test_tree("walk-ignore");
+        fs::create_dir_all(root.join(".git/info")).unwrap();
+        fs::write(root.join(".git/info/exclude"), "z.txt\n").unwrap();
+        fs::write(root.join(".gitignore"), "d/\n*.txt\n!c.txt\n").unwrap();
+        // deeper and `.ignore` files take precedence
+        fs::write(root.join("a/.gitignore"), "!h.txt\n").unwrap();
+        fs::write(root.join(".ignore"), "!z.txt\n").unwrap();
+
+        let options = WalkOptions::default();
+        assert_eq!(
+            walk_relative(&root, &options),
+            ["a/h.txt", "b/c.txt", "z.txt"]
+        );
+
+        let options = WalkOptions {
+            no_ignore: true,
+            ..WalkOptions::default()
+        };
+        assert_eq!(
+            walk_relative(&root, &options),
+            ["a/h.txt", "b/c.txt", "b/d/e.txt", "z.txt"]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_walk_outer_ignore() {
+        let root = test_tree("walk-outer-ignore");
+        fs::write(root.join("b/d/g.txt"), "g").unwrap();
+        fs::write(root.join(".gitignore"), "/b/c.txt\n").unwrap();
+        fs::write(root.join("b/.ignore"), "e.txt\n").unwrap();
+        let options = WalkOptions::default();
+
+        // ignore files above the root are only read in a git repository
+        assert_eq!(
+            walk_relative(&root.join("b/d"), &options),
+            ["e.txt", "g.txt"]
+        );
+
+        fs::create_dir_all(root.join(".git/info")).unwrap();
+        fs::write(root.join(".git/info/exclude"), "g.txt\n").unwrap();
+        // the repository is found from any directory in it
+        assert_eq!(
+            walk_relative(&root.join("b"), &options),
+            Vec::<String>::new()
+        );
+        fs::write(root.join("b/d/.gitignore"), "!e.txt\n").unwrap();
+        assert_eq!(walk_relative(&root.join("b"), &options), ["d/e.txt"]);
+        assert_eq!(walk_relative(&root.join("b/d"), &options), ["e.txt"]);
+
+        let options = WalkOptions {
+            no_ignore: true,
+            ..WalkOptions::default()
+        };
+        assert_eq!(
+            walk_relative(&root.join("b"), &options),
+            ["c.txt", "d/e.txt", "d/g.txt"]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_walk_hidden_git() {
+        let root = test_tree("walk-hidden-git");
+        fs::create_dir_all(root.join(".git/objects")).unwrap();
+        fs::write(root.join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+        fs::write(root.join(".git/objects/pack"), "").unwrap();
+        fs::write(root.join(".gitignore"), "b/\n").unwrap();
+
+        // `.git` is skipped even though other hidden files are counted
+        let options = WalkOptions {
+            hidden: true,
+            ..WalkOptions::default()
+        };
+        assert_eq!(
+            walk_relative(&root, &options),
+            [".g.txt", ".gitignore", ".hidden/f.txt", "a/h.txt", "z.txt"]
+        );
+
+        let options = WalkOptions {
+            hidden: true,
+            no_ignore: true,
+            ..WalkOptions::default()
+        };
+        assert_eq!(
+            walk_relative(&root, &options),
+            [
+                ".g.txt",
+                ".git/HEAD",
+                ".git/objects/pack",
+                ".gitignore",
+                ".hidden/f.txt",
+                "a/h.txt",
+                "b/c.txt",
+                "b/d/e.txt",
+                "z.txt"
+            ]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_walk_git_file() {
+        // in a worktree or submodule `.git` is a file pointing to the
+        // repository, so there is no `.git/info/exclude` to read
+        let root = test_tree("walk-git-file");
+        fs::write(root.join(".git"), "gitdir: ../repo/.git/worktrees/a\n").unwrap();
+        fs::write(root.join(".gitignore"), "z.txt\n").unwrap();
+        assert_eq!(
+            walk_relative(&root, &WalkOptions::default()),
+            ["a/h.txt", "b/c.txt", "b/d/e.txt"]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_symlinks() {
+        let root = test_tree("walk-symlinks");
+        std::os::unix::fs::symlink(root.join("b/d"), root.join("a/link")).unwrap();
+        // a link back to the root would be walked forever
+        std::os::unix::fs::symlink(&root, root.join("b/d/loop")).unwrap();
+
+        let options = WalkOptions::default();
+        assert_eq!(
+            walk_relative(&root, &options),
+            ["a/h.txt", "b/c.txt", "b/d/e.txt", "z.txt"]
+        );
+
+        let options = WalkOptions {
+            follow_symlinks: true,
+            ..WalkOptions::default()
+        };
+        let mut found = Walk::default();
+        walk(&root, &options, &PathFilter::default(), &mut found);
+        let files: Vec<_> = found
+            .files
+            .iter()
+            .map(|path| {
+                path.strip_prefix(&root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        assert_eq!(
+            files,
+            ["a/h.txt", "a/link/e.txt", "b/c.txt", "b/d/e.txt", "z.txt"]
+        );
+        let loops: Vec<_> = found.errors.iter().map(|(path, _)| path.clone()).collect();
+        assert_eq!(loops, [root.join("a/link/loop"), root.join("b/d/loop")]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
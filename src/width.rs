@@ -0,0 +1,136 @@
+use unicode_width::UnicodeWidthChar;
+
+/// Tab stops are every `TAB_WIDTH` columns by default, as in GNU wc.
+pub const TAB_WIDTH: usize = 8;
+
+/// The number of terminal columns `c` takes up when displayed: 2 for wide
+/// East Asian characters, 0 for combining marks and control characters,
+/// and 1 for everything else. Tabs are handled by `advance_column`.
+pub fn char_width(c: char) -> usize {
+    c.width().unwrap_or(0)
+}
+
+/// Return the column after displaying `c` at `column`, expanding tabs to
+/// the next multiple of `tab_width`.
+pub fn advance_column(column: usize, c: char, tab_width: usize) -> usize {
+    match c {
+        '\t' => column + tab_width - column % tab_width,
+        _ => column + char_width(c),
+    }
+}
+
+/// Computes the display width of lines incrementally from their characters,
+/// keeping both the total width of all lines and the width of the longest.
+/// Like GNU wc, carriage returns and form feeds also move back to the start
+/// of the line, so the text after them overwrites the text before. A line
+/// then takes up the width of its widest part, which is what is added to
+/// the total.
+#[derive(Debug, Clone)]
+pub struct ColumnCounter {
+    tab_width: usize,
+    /// the display width of the current line since the last carriage
+    /// return or form feed
+    column: usize,
+    /// the width of the widest part of the current line before the last
+    /// carriage return or form feed
+    line_max: usize,
+    total: usize,
+    max: usize,
+}
+
+impl Default for ColumnCounter {
+    fn default() -> Self {
+        Self::new(TAB_WIDTH)
+    }
+}
+
+impl ColumnCounter {
+    /// Create a `ColumnCounter` with tab stops every `tab_width` columns,
+    /// which must be at least 1.
+    pub fn new(tab_width: usize) -> Self {
+        Self {
+            tab_width,
+            column: 0,
+            line_max: 0,
+            total: 0,
+            max: 0,
+        }
+    }
+
+    /// Count the next character `c`.
+    pub fn push(&mut self, c: char) {
+        match c {
+            '\n' => self.end_line(),
+            '\r' | '\x0c' => {
+                self.line_max = self.line_max.max(self.column);
+                self.column = 0;
+            }
+            _ => self.column = advance_column(self.column, c, self.tab_width),
+        }
+    }
+
+    /// Finish the current line and start a new one.
+    pub fn end_line(&mut self) {
+        let width = self.line_max.max(self.column);
+        self.total += width;
+        self.max = self.max.max(width);
+        self.column = 0;
+        self.line_max = 0;
+    }
+
+    /// The total width of the finished lines.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// The width of the longest finished line.
+    pub fn max(&self) -> usize {
+        self.max
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_char_width() {
+        assert_eq!(char_width('a'), 1);
+        assert_eq!(char_width('日'), 2);
+        assert_eq!(char_width('\u{301}'), 0);
+        assert_eq!(char_width('\u{7}'), 0);
+    }
+
+    #[test]
+    fn test_advance_column() {
+        assert_eq!(advance_column(0, '\t', TAB_WIDTH), 8);
+        assert_eq!(advance_column(7, '\t', TAB_WIDTH), 8);
+        assert_eq!(advance_column(8, '\t', TAB_WIDTH), 16);
+        assert_eq!(advance_column(3, 'テ', TAB_WIDTH), 5);
+        assert_eq!(advance_column(5, '\t', 4), 8);
+        assert_eq!(advance_column(5, '\t', 1), 6);
+    }
+
+    #[test]
+    fn test_column_counter() {
+        let mut counter = ColumnCounter::new(4);
+        "ab\tc\n日本\re\u{301}"
+            .chars()
+            .for_each(|c| counter.push(c));
+        counter.end_line();
+        assert_eq!(counter.total(), 5 + 4);
+        assert_eq!(counter.max(), 5);
+    }
+
+    #[test]
+    fn test_column_counter_overwrite() {
+        // `xy` overwrites the start of `abcd`, which takes up 4 columns
+        let mut counter = ColumnCounter::default();
+        "abcd\rxy\nab\x0cwxyz\n"
+            .chars()
+            .for_each(|c| counter.push(c));
+        counter.end_line();
+        assert_eq!(counter.total(), 4 + 4);
+        assert_eq!(counter.max(), 4);
+    }
+}
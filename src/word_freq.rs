@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::count_options::{CountOptions, WhitespaceMode, WordMode};
+use crate::counter::{final_segments_end, is_incomplete_char};
+
+/// How words are normalized before they are counted by a `WordCounter`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct WordFreqOptions {
+    /// how many of the most frequent words to report
+    pub top: usize,
+    /// count words that only differ in case as the same word
+    pub fold_case: bool,
+    /// remove anything other than letters and digits from the start and
+    /// end of words
+    pub strip_punctuation: bool,
+    /// skip words with fewer characters than this after normalizing
+    pub min_length: usize,
+}
+
+impl Default for WordFreqOptions {
+    fn default() -> Self {
+        Self {
+            top: 10,
+            fold_case: false,
+            strip_punctuation: false,
+            min_length: 0,
+        }
+    }
+}
+
+impl WordFreqOptions {
+    /// Normalize `word`, returning `None` if it should not be counted.
+    fn normalize(&self, word: &str) -> Option<String> {
+        let word = if self.strip_punctuation {
+            word.trim_matches(|c: char| !c.is_alphanumeric())
+        } else {
+            word
+        };
+        let word = if self.fold_case {
+            word.to_lowercase()
+        } else {
+            word.to_string()
+        };
+        (!word.is_empty() && word.chars().count() >= self.min_length).then_some(word)
+    }
+}
+
+/// How many times each word was seen, see `WordCounter`.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct WordCounts {
+    counts: HashMap<String, usize>,
+}
+
+impl WordCounts {
+    /// Add the counts in `other` to `self`.
+    pub fn merge(&mut self, other: Self) {
+        for (word, count) in other.counts {
+            *self.counts.entry(word).or_default() += count;
+        }
+    }
+
+    /// The total number of words counted.
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    /// The `n` most frequent words with their counts, most frequent first
+    /// and in lexicographic order for words with the same count.
+    pub fn top(&self, n: usize) -> Vec<(String, usize)> {
+        let mut counts: Vec<(&String, &usize)> = self.counts.iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        counts
+            .into_iter()
+            .take(n)
+            .map(|(word, count)| (word.clone(), *count))
+            .collect()
+    }
+}
+
+/// The longest a word counted by a `WordCounter` can be in bytes. Longer
+/// words are counted by their first `MAX_WORD_LEN` bytes followed by `…`,
+/// so input without whitespace doesn't have to be held in memory at once.
+pub const MAX_WORD_LEN: usize = 1024;
+
+/// Counts how many times each word appears incrementally from a stream of
+/// byte chunks. Words are split the same way as `Counter` counts them, at
+/// the whitespace of `WhitespaceMode::is_whitespace` with invalid UTF-8
+/// being part of words or at Unicode word boundaries depending on the
+/// `WordMode`, so without any normalization the total of the counts is the
+/// word count. Bytes that are not valid UTF-8 are replaced with U+FFFD in
+/// the counted words.
+#[derive(Debug, Clone)]
+pub struct WordCounter {
+    options: WordFreqOptions,
+    word_mode: WordMode,
+    whitespace: WhitespaceMode,
+    counts: WordCounts,
+    /// the current word so far, at most `MAX_WORD_LEN` bytes of it
+    word: String,
+    /// whether the current word is longer than `word`
+    truncated: bool,
+    /// leading bytes of a multi-byte character whose remaining bytes
+    /// are in the next chunk
+    pending: Vec<u8>,
+    /// the end of the current line whose words aren't counted yet, only
+    /// kept with `WordMode::Uax29`
+    line: String,
+}
+
+impl WordCounter {
+    pub fn new(options: &WordFreqOptions, count_options: &CountOptions) -> Self {
+        Self {
+            options: options.clone(),
+            word_mode: count_options.word_mode,
+            whitespace: count_options.whitespace,
+            counts: WordCounts::default(),
+            word: String::new(),
+            truncated: false,
+            pending: Vec::new(),
+            line: String::new(),
+        }
+    }
+
+    /// Count the words completed by the next chunk of the input.
+    pub fn update(&mut self, mut bytes: &[u8]) {
+        // feed bytes one at a time to a character started in a previous
+        // chunk until it is either complete or known to be invalid
+        while !self.pending.is_empty() {
+            let Some((&b, rest)) = bytes.split_first() else {
+                return;
+            };
+            bytes = rest;
+            let mut pending = std::mem::take(&mut self.pending);
+            pending.push(b);
+            self.count_bytes(&pending);
+        }
+        self.count_bytes(bytes);
+        if self.word_mode == WordMode::Uax29 {
+            self.count_segments(final_segments_end(&self.line));
+        }
+    }
+
+    /// Count the words completed by `bytes`, keeping an incomplete character
+    /// at the end of `bytes` in `self.pending`.
+    fn count_bytes(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.utf8_chunks().peekable();
+        while let Some(chunk) = chunks.next() {
+            for c in chunk.valid().chars() {
+                match self.word_mode {
+                    WordMode::Whitespace if self.whitespace.is_whitespace(c) => self.end_word(),
+                    WordMode::Whitespace => self.push_word(c),
+                    WordMode::Uax29 if c == '\n' => self.count_segments(self.line.len()),
+                    WordMode::Uax29 => self.line.push(c),
+                }
+            }
+            let invalid = chunk.invalid();
+            if invalid.is_empty() {
+                continue;
+            }
+            if chunks.peek().is_none() && is_incomplete_char(invalid) {
+                self.pending.extend_from_slice(invalid);
+            } else {
+                self.push_invalid();
+            }
+        }
+    }
+
+    /// Add U+FFFD for a sequence of bytes that is not valid UTF-8.
+    fn push_invalid(&mut self) {
+        match self.word_mode {
+            WordMode::Whitespace => self.push_word(char::REPLACEMENT_CHARACTER),
+            WordMode::Uax29 => self.line.push(char::REPLACEMENT_CHARACTER),
+        }
+    }
+
+    /// Add `c` to the current word, unless it is already `MAX_WORD_LEN`
+    /// bytes long.
+    fn push_word(&mut self, c: char) {
+        if self.word.len() + c.len_utf8() <= MAX_WORD_LEN {
+            self.word.push(c);
+        } else {
+            self.truncated = true;
+        }
+    }
+
+    /// Count the current word, if any, and start a new one.
+    fn end_word(&mut self) {
+        if self.word.is_empty() {
+            return;
+        }
+        if self.truncated {
+            self.word.push('…');
+            self.truncated = false;
+        }
+        let mut word = std::mem::take(&mut self.word);
+        self.count(&word);
+        word.clear();
+        self.word = word;
+    }
+
+    /// Count the words of the current line up to `end` with a letter or
+    /// digit between Unicode word boundaries and remove them from the line.
+    fn count_segments(&mut self, end: usize) {
+        let line = std::mem::take(&mut self.line);
+        for word in line[..end].unicode_words() {
+            self.count(word);
+        }
+        self.line = line;
+        self.line.drain(..end);
+    }
+
+    /// Finish counting and return the counts of all chunks seen.
+    pub fn finish(mut self) -> WordCounts {
+        // the input ended in the middle of a character
+        if !self.pending.is_empty() {
+            self.pending.clear();
+            self.push_invalid();
+        }
+        match self.word_mode {
+            WordMode::Whitespace => self.end_word(),
+            WordMode::Uax29 => self.count_segments(self.line.len()),
+        }
+        self.counts
+    }
+
+    fn count(&mut self, word: &str) {
+        if let Some(word) = self.options.normalize(word) {
+            *self.counts.counts.entry(word).or_default() += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::file_result::counts_for_file;
+
+    /// Count the words of `s` with `options`, feeding it to a `WordCounter`
+    /// in chunks of `size` bytes.
+    fn counts_in_chunks(s: &[u8], size: usize, options: &WordFreqOptions) -> WordCounts {
+        let mut counter = WordCounter::new(options, &CountOptions::default());
+        for chunk in s.chunks(size) {
+            counter.update(chunk);
+        }
+        counter.finish()
+    }
+
+    #[test]
+    fn test_split_chunks() {
+        let s = "the cat\u{3000}saw the\ndog, the Cat \u{a0}日本語 ".as_bytes();
+        let s = &[s, b"\xff"].concat()[..];
+        let options = WordFreqOptions::default();
+        let expected = counts_in_chunks(s, s.len(), &options);
+        assert_eq!(expected.total(), counts_for_file(s).words);
+        for size in 1..s.len() {
+            assert_eq!(
+                counts_in_chunks(s, size, &options),
+                expected,
+                "chunk size {size}"
+            );
+        }
+        assert_eq!(
+            expected.top(3),
+            [
+                (String::from("the"), 3),
+                (String::from("Cat"), 1),
+                (String::from("cat"), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        // invalid bytes are part of words, and a character split across
+        // chunks is decoded once it is complete
+        let s = b"caf\xc3\xa9 caf\xe9 \xe2\x82";
+        let counts = counts_in_chunks(s, 1, &WordFreqOptions::default());
+        assert_eq!(counts.total(), counts_for_file(s).words);
+        assert_eq!(
+            counts.top(5),
+            [
+                (String::from("café"), 1),
+                (String::from("caf\u{fffd}"), 1),
+                (String::from("\u{fffd}"), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_normalize() {
+        let s = "The cat, the CAT! (the) -- a";
+        let options = WordFreqOptions {
+            fold_case: true,
+            strip_punctuation: true,
+            min_length: 2,
+            ..WordFreqOptions::default()
+        };
+        let counts = counts_in_chunks(s.as_bytes(), 4, &options);
+        assert_eq!(
+            counts.top(10),
+            [(String::from("the"), 3), (String::from("cat"), 2)]
+        );
+    }
+
+    #[test]
+    fn test_long_words() {
+        let long = "x".repeat(MAX_WORD_LEN);
+        let longer = format!("{long}y");
+        let s = format!("{long} {longer}\u{3000}{longer}z é{long}");
+        let counts = counts_in_chunks(s.as_bytes(), 7, &WordFreqOptions::default());
+        assert_eq!(counts.total(), 4);
+        assert_eq!(
+            counts.top(3),
+            [
+                (format!("{long}…"), 2),
+                (long.clone(), 1),
+                (format!("é{}…", &long[2..]), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_word_mode_uax29_long_line() {
+        let count_options = CountOptions {
+            word_mode: WordMode::Uax29,
+            ..CountOptions::default()
+        };
+        let options = WordFreqOptions::default();
+        let s = "can't 3.14 a.b x\u{301}y\u{301} 🇫🇷🇩🇪 日本 \u{5d0}\"\u{5d1} ".repeat(2000);
+        let mut counter = WordCounter::new(&options, &count_options);
+        counter.update(s.as_bytes());
+        let expected = counter.finish();
+        assert_eq!(expected.total(), 7 * 2000);
+        assert_eq!(expected.top(1), [(String::from("3.14"), 2000)]);
+        for size in [1, 3, 64] {
+            let mut counter = WordCounter::new(&options, &count_options);
+            for chunk in s.as_bytes().chunks(size) {
+                counter.update(chunk);
+                assert!(counter.line.len() < 64, "chunk size {size}");
+            }
+            assert_eq!(counter.finish(), expected, "chunk size {size}");
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let options = WordFreqOptions::default();
+        let mut counts = counts_in_chunks(b"a b a", 2, &options);
+        counts.merge(counts_in_chunks(b"b c b", 2, &options));
+        assert_eq!(counts.total(), 6);
+        assert_eq!(
+            counts.top(2),
+            [(String::from("b"), 3), (String::from("a"), 2)]
+        );
+    }
+}
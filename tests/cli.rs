@@ -0,0 +1,69 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Create a directory for testing under the temporary directory, named
+/// after `name` and the process id, with the files `files` in it.
+fn test_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("wcrs-cli-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for (file, contents) in files {
+        fs::write(dir.join(file), contents).unwrap();
+    }
+    dir
+}
+
+/// Run wcrs in `dir` with `args` and `stdin`, returning what it printed.
+fn wcrs(dir: &Path, args: &[&str], stdin: &[u8]) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_wcrs"))
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_files0_from_stdin() {
+    let dir = test_dir(
+        "files0-from",
+        &[("a.txt", "hello world\nfoo\n"), ("b.txt", "x\n")],
+    );
+    // like GNU wc, the inputs aren't inspected for the width of the columns
+    // when their names come from stdin
+    assert_eq!(
+        wcrs(&dir, &["--files0-from=-"], b"a.txt\0b.txt\0"),
+        "2 3 16 a.txt\n1 1 2 b.txt\n3 4 18 total\n"
+    );
+    fs::write(dir.join("list"), b"a.txt\0b.txt\0").unwrap();
+    assert_eq!(
+        wcrs(&dir, &["--files0-from=list"], b""),
+        " 2  3 16 a.txt\n 1  1  2 b.txt\n 3  4 18 total\n"
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_extras_backends() {
+    let dir = test_dir(
+        "extras",
+        &[("a.rs", "// one\nfn two() {}\n"), ("b.txt", "x y x\n")],
+    );
+    let args = ["--code-stats", "--word-freq=2", "a.rs", "b.txt"];
+    let expected = wcrs(&dir, &args, b"");
+    assert!(expected.contains("Rust         1    1        1      0\n"));
+    for backend in ["--mmap", "--split-files"] {
+        let args: Vec<&str> = [backend, "--jobs=2"].into_iter().chain(args).collect();
+        assert_eq!(wcrs(&dir, &args, b""), expected, "{backend}");
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
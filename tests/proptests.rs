@@ -1,10 +1,18 @@
+use proptest::arbitrary::any;
 use proptest::strategy::{Just, Strategy};
 use proptest::{char, collection};
 use proptest::{prop_oneof, proptest};
 
 use rand::Rng;
+use unicode_segmentation::UnicodeSegmentation;
 
-use wcrs::file_result::{counts_for_file, counts_for_line};
+use wcrs::count_options::{CountOptions, LineTerminator, WhitespaceMode, WordMode};
+use wcrs::counter::Counter;
+use wcrs::display_options::DisplayOptions;
+use wcrs::file_result::{counts_for_file, counts_for_line, FileResult};
+use wcrs::newlines::count_newlines;
+use wcrs::partial::PartialResult;
+use wcrs::word_freq::{WordCounter, WordFreqOptions};
 
 #[derive(Debug, Clone)]
 struct WordData {
@@ -25,6 +33,8 @@ struct LineData {
 struct FileData {
     file: String,
     lines: usize,
+    blank_lines: usize,
+    non_blank_lines: usize,
     words: usize,
     chars: usize,
     bytes: usize,
@@ -33,12 +43,12 @@ struct FileData {
 /// Produces a string of non-whitespace characters of length at most `max_length`
 fn word_data_strategy(max_length: usize) -> impl Strategy<Value = WordData> {
     // char::any() can produce strings with whitespaces so we replace them with a
-    // random alphanumeric character
+    // random alphanumeric character. Unicode whitespace includes the whitespace
+    // of every `WhitespaceMode`, so the result is a single word in all of them
     collection::vec(char::any(), 1..max_length).prop_flat_map(|mut v| {
         let mut rng = rand::rng();
         v.iter_mut().for_each(|c| {
-            // TODO: use is_ascii_whitespace() instead?
-            if c.is_whitespace() {
+            if WhitespaceMode::Unicode.is_whitespace(*c) {
                 *c = rng.sample(rand::distr::Alphanumeric) as char;
             }
         });
@@ -87,7 +97,7 @@ fn line_data_strategy(
             let line = wdv
                 .iter()
                 .zip(spacesv.iter())
-                .fold(String::new(), |acc, (wd, s)| acc + &wd.word + &s);
+                .fold(String::new(), |acc, (wd, s)| acc + &wd.word + s);
 
             // the spaces strings consist of space and tab characters, so its byte count and char count is equal
             let spaces_bytes = spacesv.iter().fold(0, |acc, s| acc + s.len());
@@ -103,11 +113,27 @@ fn line_data_strategy(
         })
 }
 
+/// Produces a blank line, i.e. a possibly empty string of spaces and tabs of length
+/// less than `max_length`.
+fn blank_line_data_strategy(max_length: usize) -> impl Strategy<Value = LineData> {
+    collection::vec(space_tab_strategy(), 0..max_length).prop_map(|v| {
+        let line: String = v.into_iter().collect();
+        let chars = line.len();
+        let bytes = line.len();
+        LineData {
+            line,
+            words: 0,
+            chars,
+            bytes,
+        }
+    })
+}
+
 /// Produces a string representing a file i.e. a string including multiple substrings
 /// separated by newlines. The string will have at most `max_lines` newlines, each line
-/// of the string consists of `max_num_components` words separated by whitespaces,
-/// the words have size at most `max_word_length` and the whitespaces between
-/// words have size at most `max_whitespace_length`.
+/// of the string is either blank or consists of `max_num_components` words separated
+/// by whitespaces, the words have size at most `max_word_length` and the whitespaces
+/// between words have size at most `max_whitespace_length`.
 fn file_data_strategy(
     max_lines: usize,
     max_num_components: usize,
@@ -115,12 +141,17 @@ fn file_data_strategy(
     max_whitespace_length: usize,
 ) -> impl Strategy<Value = FileData> {
     collection::vec(
-        line_data_strategy(max_num_components, max_word_length, max_whitespace_length),
+        prop_oneof![
+            3 => line_data_strategy(max_num_components, max_word_length, max_whitespace_length),
+            1 => blank_line_data_strategy(max_whitespace_length),
+        ],
         1..max_lines,
     )
     .prop_flat_map(|v| {
         let words = v.iter().fold(0, |acc, l| acc + l.words);
         let lines = v.len();
+        let blank_lines = v.iter().filter(|l| l.words == 0).count();
+        let non_blank_lines = lines - blank_lines;
         // we add a newline '\n' at the end of each line
         // so we need to add the number of lines to chars and bytes
         let chars = v.iter().fold(0, |acc, l| acc + l.chars) + lines;
@@ -131,6 +162,8 @@ fn file_data_strategy(
         Just(FileData {
             file,
             lines,
+            blank_lines,
+            non_blank_lines,
             words,
             chars,
             bytes,
@@ -138,6 +171,117 @@ fn file_data_strategy(
     })
 }
 
+/// Produces a line of at most `max_num_words` words made of ASCII letters and
+/// digits, separated by spaces and tabs, along with its number of words.
+fn ascii_line_strategy(max_num_words: usize) -> impl Strategy<Value = (String, usize)> {
+    (
+        collection::vec("[a-zA-Z0-9]{1,10}", 0..max_num_words),
+        whitespace_strategy(5),
+    )
+        .prop_map(|(words, whitespace)| (words.join(&whitespace), words.len()))
+}
+
+/// Count the words of `bytes` with `word_mode`, feeding them to a `Counter`
+/// in chunks of `chunk_size` bytes.
+fn words_in_chunks(bytes: &[u8], chunk_size: usize, word_mode: WordMode) -> usize {
+    let count_options = CountOptions {
+        word_mode,
+        ..CountOptions::default()
+    };
+    let mut counter = Counter::for_options(&DisplayOptions::with_words_only(), &count_options);
+    for chunk in bytes.chunks(chunk_size) {
+        counter.update(chunk);
+    }
+    counter.finish().words
+}
+
+/// Produces a string of at most `max_length` characters that often has
+/// whitespace which only some `WhitespaceMode`s treat as whitespace.
+fn mixed_whitespace_strategy(max_length: usize) -> impl Strategy<Value = String> {
+    collection::vec(
+        prop_oneof![
+            char::any(),
+            Just(' '),
+            Just('\n'),
+            Just('\x0b'),
+            Just('\u{85}'),
+            Just('\u{a0}'),
+            Just('\u{3000}'),
+        ],
+        0..max_length,
+    )
+    .prop_map(|v| v.into_iter().collect())
+}
+
+/// Produces a string of at most `max_length` arbitrary characters mixed with
+/// every kind of line ending.
+fn line_endings_strategy(max_length: usize) -> impl Strategy<Value = String> {
+    collection::vec(
+        prop_oneof![
+            char::any().prop_map(String::from),
+            Just(String::from(" ")),
+            Just(String::from("\n")),
+            Just(String::from("\r")),
+            Just(String::from("\r\n")),
+            Just(String::from("\u{85}")),
+            Just(String::from("\u{2028}")),
+            Just(String::from("\u{2029}")),
+        ],
+        0..max_length,
+    )
+    .prop_map(|v| v.concat())
+}
+
+/// Count `bytes` with `line_terminator`, displaying the default counts, the
+/// blank and non-blank lines and the line endings, feeding them to a
+/// `Counter` in chunks of `chunk_size` bytes.
+fn line_endings_in_chunks(
+    bytes: &[u8],
+    line_terminator: LineTerminator,
+    chunk_size: usize,
+) -> FileResult {
+    let options = DisplayOptions::default_options()
+        .join(&DisplayOptions::with_blank_lines_only())
+        .join(&DisplayOptions::with_non_blank_lines_only())
+        .join(&DisplayOptions::with_line_endings_only());
+    let count_options = CountOptions {
+        line_terminator,
+        ..CountOptions::default()
+    };
+    let mut counter = Counter::for_options(&options, &count_options);
+    for chunk in bytes.chunks(chunk_size) {
+        counter.update(chunk);
+    }
+    counter.finish()
+}
+
+/// Count the grapheme clusters of `bytes`, feeding them to a `Counter` in
+/// chunks of `chunk_size` bytes.
+fn graphemes_in_chunks(bytes: &[u8], chunk_size: usize) -> usize {
+    let options = DisplayOptions::with_graphemes_only();
+    let mut counter = Counter::for_options(&options, &CountOptions::default());
+    for chunk in bytes.chunks(chunk_size) {
+        counter.update(chunk);
+    }
+    counter.finish().graphemes
+}
+
+/// Produces a byte string of at most `max_length` pieces, where each piece is
+/// either an arbitrary byte, the UTF-8 encoding of an arbitrary character, or
+/// whitespace, so that the result mixes valid and invalid UTF-8.
+fn mixed_bytes_strategy(max_length: usize) -> impl Strategy<Value = Vec<u8>> {
+    collection::vec(
+        prop_oneof![
+            any::<u8>().prop_map(|b| vec![b]),
+            char::any().prop_map(|c| c.to_string().into_bytes()),
+            space_tab_strategy().prop_map(|c| c.to_string().into_bytes()),
+            Just(b"\n".to_vec()),
+        ],
+        0..max_length,
+    )
+    .prop_map(|v| v.concat())
+}
+
 proptest! {
     #[test]
     fn test_word_strategy(wd in word_data_strategy(10)) {
@@ -181,11 +325,47 @@ proptest! {
         let words = &ld.words;
         let chars = &ld.chars;
         let bytes = &ld.bytes;
-        let result = counts_for_line(&line);
+        let result = counts_for_line(line, &CountOptions::default());
         dbg!(line, &result);
         assert_eq!(result.words, *words);
         assert_eq!(result.chars, *chars);
         assert_eq!(result.bytes, *bytes);
+        assert_eq!(result.blank_lines, 0);
+        assert_eq!(result.non_blank_lines, 1);
+        // the display width is the same as for the line on its own
+        let file = counts_for_file(line.as_bytes());
+        assert_eq!(result.columns, file.columns);
+        assert_eq!(result.max_line_length, file.max_line_length);
+    }
+
+    #[test]
+    fn test_counts_for_blank_line(ld in blank_line_data_strategy(20)) {
+        let line = &ld.line;
+        let result = counts_for_line(line, &CountOptions::default());
+        dbg!(line, &result);
+        assert_eq!(result.words, 0);
+        assert_eq!(result.chars, ld.chars);
+        assert_eq!(result.blank_lines, 1);
+        assert_eq!(result.non_blank_lines, 0);
+        assert_eq!(result.columns, counts_for_file(line.as_bytes()).columns);
+    }
+
+    #[test]
+    fn test_columns_tab_width(fd in file_data_strategy(10, 10, 10, 5), tab_width in 1..16usize) {
+        let file = fd.file.as_bytes();
+        let count_options = CountOptions { tab_width, ..CountOptions::default() };
+        let mut counter = Counter::for_options(&DisplayOptions::with_columns_only(), &count_options);
+        counter.update(file);
+        let result = counter.finish();
+        dbg!(&fd.file, &result);
+        // a tab takes up between 1 and `tab_width` columns
+        let tabs = fd.file.matches('\t').count();
+        let other: usize = fd.file.lines().map(|line| counts_for_line(&line.replace('\t', ""), &count_options).columns).sum();
+        assert!(result.columns >= other + tabs);
+        assert!(result.columns <= other + tabs * tab_width);
+        if tab_width == 1 {
+            assert_eq!(result.columns, other + tabs);
+        }
     }
 
     #[test]
@@ -195,12 +375,260 @@ proptest! {
         let words = &fd.words;
         let chars = &fd.chars;
         let bytes = &fd.bytes;
-        let result = counts_for_file(&file);
+        let result = counts_for_file(file.as_bytes());
         dbg!(file, &result);
         assert_eq!(result.lines, *lines);
+        assert_eq!(result.blank_lines, fd.blank_lines);
+        assert_eq!(result.non_blank_lines, fd.non_blank_lines);
         assert_eq!(result.words, *words);
         assert_eq!(result.chars, *chars);
         assert_eq!(result.bytes, *bytes);
     }
 
+    #[test]
+    fn test_counter_chunks(fd in file_data_strategy(30, 15, 10, 5), chunk_size in 1..64usize) {
+        let file = &fd.file;
+        let mut counter = Counter::new();
+        for chunk in file.as_bytes().chunks(chunk_size) {
+            counter.update(chunk);
+        }
+        let result = counter.finish();
+        dbg!(file, &result);
+        assert_eq!(result, counts_for_file(file.as_bytes()));
+    }
+
+    #[test]
+    fn test_counter_arbitrary_bytes(bytes in collection::vec(any::<u8>(), 0..256), chunk_size in 1..16usize) {
+        let mut counter = Counter::new();
+        for chunk in bytes.chunks(chunk_size) {
+            counter.update(chunk);
+        }
+        let result = counter.finish();
+        dbg!(&bytes, &result);
+        // only bytes in valid UTF-8 sequences are characters
+        let chars: usize = bytes.utf8_chunks().map(|c| c.valid().chars().count()).sum();
+        assert_eq!(result.chars, chars);
+        assert_eq!(result.bytes, bytes.len());
+        assert_eq!(result, counts_for_file(&bytes));
+    }
+
+    #[test]
+    fn test_partial_merge_associative(
+        bytes in mixed_bytes_strategy(32),
+        i in 0..128usize,
+        j in 0..128usize,
+    ) {
+        let i = i.min(bytes.len());
+        let j = j.clamp(i, bytes.len());
+        let a = PartialResult::of(&bytes[..i]);
+        let b = PartialResult::of(&bytes[i..j]);
+        let c = PartialResult::of(&bytes[j..]);
+        dbg!(&bytes, i, j);
+        assert_eq!(a.merge(&b).merge(&c), a.merge(&b.merge(&c)));
+        assert_eq!(a.merge(&b).merge(&c), PartialResult::of(&bytes));
+    }
+
+    #[test]
+    fn test_partial_matches_sequential(
+        bytes in mixed_bytes_strategy(128),
+        chunk_size in 1..16usize,
+    ) {
+        let merged = bytes
+            .chunks(chunk_size)
+            .fold(PartialResult::default(), |acc, chunk| acc.merge(&PartialResult::of(chunk)));
+        dbg!(&bytes, &merged);
+        // partial results don't compute the maximum line length
+        let expected = FileResult { max_line_length: 0, columns: 0, ..counts_for_file(&bytes) };
+        assert_eq!(merged.finish(), expected);
+    }
+
+    #[test]
+    fn test_partial_file(fd in file_data_strategy(30, 15, 10, 5), chunk_size in 1..64usize) {
+        let file = &fd.file;
+        // merge the chunks as a balanced tree rather than left to right
+        let mut partials: Vec<PartialResult> =
+            file.as_bytes().chunks(chunk_size).map(PartialResult::of).collect();
+        while partials.len() > 1 {
+            partials = partials
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => a.merge(b),
+                    [a] => a.clone(),
+                    _ => unreachable!(),
+                })
+                .collect();
+        }
+        let result = partials.pop().unwrap_or_default().finish();
+        dbg!(file, &result);
+        assert_eq!(result.lines, fd.lines);
+        assert_eq!(result.blank_lines, fd.blank_lines);
+        assert_eq!(result.non_blank_lines, fd.non_blank_lines);
+        assert_eq!(result.words, fd.words);
+        assert_eq!(result.chars, fd.chars);
+        assert_eq!(result.bytes, fd.bytes);
+    }
+
+    #[test]
+    fn test_distinct_words_chunks(
+        bytes in mixed_bytes_strategy(128),
+        chunk_size in 1..16usize,
+        precision in 4..8u8,
+    ) {
+        let options = DisplayOptions::with_distinct_words_only(precision);
+        let mut counter = Counter::for_options(&options, &CountOptions::default());
+        counter.update(&bytes);
+        let expected = counter.finish().distinct_words;
+        let mut counter = Counter::for_options(&options, &CountOptions::default());
+        for chunk in bytes.chunks(chunk_size) {
+            counter.update(chunk);
+        }
+        dbg!(&bytes);
+        assert_eq!(counter.finish().distinct_words, expected.clone());
+        // byte ranges split anywhere, including inside a word or character
+        let merged = bytes
+            .chunks(chunk_size)
+            .map(|chunk| PartialResult::with_distinct_words(chunk, precision))
+            .fold(PartialResult::with_distinct_words(&[], precision), |acc, partial| {
+                acc.merge(&partial)
+            });
+        assert_eq!(merged.finish().distinct_words, expected);
+    }
+
+    #[test]
+    fn test_word_counter_total(bytes in mixed_bytes_strategy(128), chunk_size in 1..16usize) {
+        let mut counter = WordCounter::new(&WordFreqOptions::default(), &CountOptions::default());
+        for chunk in bytes.chunks(chunk_size) {
+            counter.update(chunk);
+        }
+        let counts = counter.finish();
+        dbg!(&bytes, &counts);
+        // without normalization every word is counted
+        assert_eq!(counts.total(), counts_for_file(&bytes).words);
+    }
+
+    #[test]
+    fn test_word_modes_ascii((line, words) in ascii_line_strategy(20), chunk_size in 1..16usize) {
+        // words of letters and digits split at whitespace are the same in
+        // both modes
+        for word_mode in [WordMode::Whitespace, WordMode::Uax29] {
+            assert_eq!(words_in_chunks(line.as_bytes(), chunk_size, word_mode), words);
+        }
+    }
+
+    #[test]
+    fn test_uax29_punctuation(s in "[-.,;:!?()\"' \t\n]{0,40}") {
+        assert_eq!(words_in_chunks(s.as_bytes(), 64, WordMode::Uax29), 0);
+        let words = s.split_whitespace().count();
+        assert_eq!(words_in_chunks(s.as_bytes(), 64, WordMode::Whitespace), words);
+    }
+
+    #[test]
+    fn test_word_modes_chunks(bytes in mixed_bytes_strategy(128), chunk_size in 1..16usize) {
+        for word_mode in [WordMode::Whitespace, WordMode::Uax29] {
+            let words = words_in_chunks(&bytes, bytes.len().max(1), word_mode);
+            dbg!(&bytes, word_mode);
+            assert_eq!(words_in_chunks(&bytes, chunk_size, word_mode), words);
+            // the word frequencies split words the same way
+            let count_options = CountOptions { word_mode, ..CountOptions::default() };
+            let mut counter = WordCounter::new(&WordFreqOptions::default(), &count_options);
+            for chunk in bytes.chunks(chunk_size) {
+                counter.update(chunk);
+            }
+            assert_eq!(counter.finish().total(), words);
+        }
+    }
+
+    #[test]
+    fn test_graphemes_chunks(
+        s in collection::vec(prop_oneof![char::any(), Just('\u{200d}'), Just('\u{301}')], 0..64),
+        chunk_size in 1..16usize,
+    ) {
+        let s: String = s.into_iter().collect();
+        assert_eq!(graphemes_in_chunks(s.as_bytes(), chunk_size), s.graphemes(true).count());
+    }
+
+    #[test]
+    fn test_graphemes_arbitrary_bytes(bytes in mixed_bytes_strategy(128), chunk_size in 1..16usize) {
+        let graphemes = graphemes_in_chunks(&bytes, bytes.len().max(1));
+        dbg!(&bytes);
+        assert_eq!(graphemes_in_chunks(&bytes, chunk_size), graphemes);
+        // every grapheme cluster has at least one character
+        assert!(graphemes <= counts_for_file(&bytes).chars);
+    }
+
+    #[test]
+    fn test_whitespace_modes(s in mixed_whitespace_strategy(64), chunk_size in 1..16usize) {
+        for whitespace in [WhitespaceMode::Unicode, WhitespaceMode::Ascii, WhitespaceMode::Posix] {
+            let count_options = CountOptions { whitespace, ..CountOptions::default() };
+            let mut counter = Counter::for_options(&DisplayOptions::new(true, true, true, true), &count_options);
+            for chunk in s.as_bytes().chunks(chunk_size) {
+                counter.update(chunk);
+            }
+            let result = counter.finish();
+            // replacing the characters that are only whitespace in Unicode
+            // gives the same words and blank lines in Unicode mode
+            let replaced: String = s
+                .chars()
+                .map(|c| if c.is_whitespace() && !whitespace.is_whitespace(c) { 'x' } else { c })
+                .collect();
+            let expected = counts_for_file(replaced.as_bytes());
+            assert_eq!(result.words, expected.words);
+            assert_eq!(result.blank_lines, expected.blank_lines);
+            assert_eq!(result.chars, s.chars().count());
+            // lines split the same way in every mode
+            for line in s.split('\n') {
+                let line_result = counts_for_line(line, &count_options);
+                let replaced: String = line
+                    .chars()
+                    .map(|c| if c.is_whitespace() && !whitespace.is_whitespace(c) { 'x' } else { c })
+                    .collect();
+                assert_eq!(line_result.words, counts_for_line(&replaced, &CountOptions::default()).words);
+            }
+        }
+    }
+
+    #[test]
+    fn test_count_newlines(bytes in mixed_bytes_strategy(256)) {
+        let newlines = bytes.iter().filter(|&&b| b == b'\n').count();
+        assert_eq!(count_newlines(&bytes), newlines);
+    }
+
+    #[test]
+    fn test_line_terminators(s in line_endings_strategy(64), chunk_size in 1..16usize) {
+        let bytes = s.as_bytes();
+        let result = line_endings_in_chunks(bytes, LineTerminator::Lf, bytes.len().max(1));
+        let crlf = s.matches("\r\n").count();
+        assert_eq!(result.crlf_endings, crlf);
+        assert_eq!(result.lf_endings, s.matches('\n').count() - crlf);
+        assert_eq!(result.cr_endings, s.matches('\r').count() - crlf);
+        let unicode = s.matches(['\u{85}', '\u{2028}', '\u{2029}']).count();
+        assert_eq!(result.unicode_endings, unicode);
+        let expected = counts_for_file(bytes);
+        assert_eq!(result.lines, expected.lines);
+        assert_eq!(result.blank_lines, expected.blank_lines);
+
+        let endings = [result.lf_endings, result.crlf_endings, result.cr_endings, result.unicode_endings];
+        let modes = [
+            (LineTerminator::Lf, endings[0] + endings[1], s.ends_with('\n')),
+            (LineTerminator::Crlf, endings[1], s.ends_with("\r\n")),
+            (LineTerminator::Cr, endings[1] + endings[2], s.ends_with('\r')),
+            (
+                LineTerminator::Any,
+                endings.iter().sum(),
+                s.ends_with(['\n', '\r', '\u{85}', '\u{2028}', '\u{2029}']),
+            ),
+        ];
+        for (line_terminator, terminators, terminated) in modes {
+            let mode_result = line_endings_in_chunks(bytes, line_terminator, chunk_size);
+            assert_eq!(&mode_result, &line_endings_in_chunks(bytes, line_terminator, bytes.len().max(1)));
+            // a final line without a terminator still counts as a line
+            assert_eq!(mode_result.lines, terminators + usize::from(!s.is_empty() && !terminated));
+            assert_eq!(mode_result.blank_lines + mode_result.non_blank_lines, mode_result.lines);
+            // only the lines depend on the line terminator
+            assert_eq!(
+                FileResult { lines: 0, blank_lines: 0, non_blank_lines: 0, ..mode_result },
+                FileResult { lines: 0, blank_lines: 0, non_blank_lines: 0, ..result.clone() }
+            );
+        }
+    }
 }